@@ -0,0 +1,178 @@
+use crate::{
+    formula::{Atom, Formula},
+    isotope::Pattern,
+    widget::atom::C,
+};
+use egui::util::cache::{ComputerMut, FrameCache};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+/// Heteroatom sets told apart by the M+2/M ratio (from the simplest)
+const HETEROATOMS: [&str; 10] = ["", "S", "S2", "Si", "Cl", "Cl2", "Cl3", "Br", "Br2", "BrCl"];
+
+/// Clustered
+pub(super) type Clustered = FrameCache<Vec<Cluster>, Clusterer>;
+
+/// Clusterer
+#[derive(Default)]
+pub(super) struct Clusterer;
+
+impl ComputerMut<(&BTreeMap<usize, u64>, u8), Vec<Cluster>> for Clusterer {
+    fn compute(&mut self, (peaks, threshold): (&BTreeMap<usize, u64>, u8)) -> Vec<Cluster> {
+        Cluster::find(peaks, threshold)
+    }
+}
+
+/// Isotope cluster (M, M+1 and M+2 peaks)
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Cluster {
+    pub(super) mass: usize,
+    /// Intensities of M, M+1 and M+2
+    pub(super) intensities: [u64; 3],
+    /// Carbon count estimated from the M+1/M ratio
+    pub(super) carbons: usize,
+    /// Heteroatoms flagged by the M+2/M ratio
+    pub(super) heteroatoms: Formula,
+}
+
+impl Cluster {
+    /// Clusters of the peaks above the noise `threshold` (in percent of the
+    /// base peak) followed by a weaker M+1 peak
+    pub(super) fn find(peaks: &BTreeMap<usize, u64>, threshold: u8) -> Vec<Self> {
+        let Some(base) = peaks.values().copied().max() else {
+            return Vec::new();
+        };
+        let threshold = base * threshold as u64 / 100;
+        let intensity = |mass: usize| peaks.get(&mass).copied().unwrap_or_default();
+        let mut clusters = Vec::new();
+        let mut members = BTreeSet::new();
+        for (&mass, &m) in peaks {
+            if m < threshold || m == 0 || members.contains(&mass) {
+                continue;
+            }
+            let intensities = [m, intensity(mass + 1), intensity(mass + 2)];
+            if intensities[1] == 0
+                || intensities[1] >= m
+                || mass
+                    .checked_sub(1)
+                    .is_some_and(|lighter| intensity(lighter) >= m)
+            {
+                continue;
+            }
+            let cluster = Self::estimate(mass, intensities);
+            members.insert(mass + 1);
+            if cluster.heteroatoms != Formula::default() {
+                members.insert(mass + 2);
+            }
+            clusters.push(cluster);
+        }
+        clusters
+    }
+
+    /// Heteroatoms best explaining the M+2/M ratio, and the carbon count of
+    /// the M+1/M ratio left after their contribution
+    fn estimate(mass: usize, intensities: [u64; 3]) -> Self {
+        let ratio = |index: usize| intensities[index] as f64 / intensities[0] as f64;
+        let observed = [ratio(1), ratio(2)];
+        let carbon = C.isotope(13).map_or(0.0, |isotope| isotope.abundance)
+            / C.most_abundant().map_or(1.0, |isotope| isotope.abundance);
+        let mut best: Option<(f64, usize, Formula)> = None;
+        for symbols in HETEROATOMS {
+            let heteroatoms = symbols.parse::<Formula>().unwrap_or_default();
+            let [m1, _] = ratios(&heteroatoms);
+            let carbons = ((observed[0] - m1) / carbon).round().max(0.0) as usize;
+            let mut formula = heteroatoms.clone();
+            if carbons > 0 {
+                let atom = Atom {
+                    element: C,
+                    mass_number: None,
+                };
+                *formula.atoms.entry(atom).or_default() += carbons;
+            }
+            let expected = ratios(&formula);
+            let error = (observed[0] - expected[0]).powi(2) + (observed[1] - expected[1]).powi(2);
+            match best {
+                Some((lowest, ..)) if lowest <= error => {}
+                _ => best = Some((error, carbons, heteroatoms)),
+            }
+        }
+        let (_, carbons, heteroatoms) = best.unwrap_or_default();
+        Self {
+            mass,
+            intensities,
+            carbons,
+            heteroatoms,
+        }
+    }
+}
+
+impl Display for Cluster {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "C{}", self.carbons)?;
+        if self.heteroatoms != Formula::default() {
+            write!(f, " {}", self.heteroatoms)?;
+        }
+        Ok(())
+    }
+}
+
+/// M+1/M and M+2/M ratios of the theoretical pattern
+fn ratios(formula: &Formula) -> [f64; 2] {
+    let nominal = Pattern::new(formula).nominal();
+    let mass = formula.nominal();
+    let ratio = |offset: usize| {
+        let m = nominal.get(&mass).copied().unwrap_or(1.0);
+        nominal.get(&(mass + offset)).copied().unwrap_or_default() / m
+    };
+    [ratio(1), ratio(2)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cluster(formula: &str, mass: usize) -> BTreeMap<usize, u64> {
+        let formula = formula.parse::<Formula>().unwrap();
+        Pattern::new(&formula)
+            .nominal()
+            .into_iter()
+            .map(|(nominal, abundance)| {
+                (
+                    nominal - formula.nominal() + mass,
+                    (abundance * 999.0).round() as u64,
+                )
+            })
+            .filter(|&(_, intensity)| intensity > 0)
+            .collect()
+    }
+
+    #[test]
+    fn find() {
+        let mut peaks = cluster("C12H25", 169);
+        peaks.extend(cluster("C6H4Cl", 111));
+        peaks.extend(cluster("C7H6Br", 169 + 20));
+        let clusters = Cluster::find(&peaks, 1);
+        let found = clusters
+            .iter()
+            .map(|cluster| (cluster.mass, cluster.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                (111, "C6 Cl".to_owned()),
+                (169, "C12".to_owned()),
+                (189, "C7 Br".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sulfur() {
+        let peaks = cluster("C8H8S", 136);
+        let clusters = Cluster::find(&peaks, 1);
+        assert_eq!(clusters[0].carbons, 8);
+        assert_eq!(clusters[0].heteroatoms.to_string(), "S");
+    }
+}
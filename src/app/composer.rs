@@ -0,0 +1,369 @@
+//! Elemental composition of an ion mass.
+//!
+//! Formulas are enumerated within the element ranges (hydrogen is solved from
+//! the mass), filtered by the ring plus double bond equivalent (RDB), the
+//! nitrogen rule and the Senior rules, and ranked by the fit of their isotope
+//! pattern to the observed peaks.
+
+use crate::{
+    formula::{Atom, Formula, ELECTRON},
+    isotope::{Fit, Pattern},
+    widget::atom::{Element, H},
+};
+use egui::util::cache::{ComputerMut, FrameCache};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+};
+
+/// Composed
+pub(super) type Composed = FrameCache<Vec<Candidate>, Composer>;
+
+/// Composer key
+#[derive(Clone, Copy, Debug, Hash)]
+pub(super) struct ComposerKey<'a> {
+    pub(super) composition: &'a Composition,
+    pub(super) peaks: &'a BTreeMap<usize, u64>,
+}
+
+/// Composer
+#[derive(Default)]
+pub(super) struct Composer;
+
+impl ComputerMut<ComposerKey<'_>, Vec<Candidate>> for Composer {
+    fn compute(&mut self, args: ComposerKey) -> Vec<Candidate> {
+        let mut candidates = args.composition.candidates();
+        let mass = args.composition.mass.round() as usize;
+        for candidate in &mut candidates {
+            candidate.fit = Pattern::new(&candidate.formula).fit(args.peaks, mass);
+        }
+        candidates.sort_by_key(|candidate| {
+            (
+                Reverse(ordered(candidate.fit.score)),
+                ordered(candidate.error.abs()),
+            )
+        });
+        candidates.truncate(args.composition.count);
+        candidates
+    }
+}
+
+/// Composition settings
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub(super) struct Composition {
+    pub(super) show: bool,
+    /// Ion mass (of a radical cation)
+    pub(super) mass: f64,
+    /// Tolerance in ppm of an accurate mass (`None` for a nominal mass)
+    pub(super) ppm: Option<f64>,
+    /// Element ranges (the hydrogen count is solved from the mass)
+    pub(super) ranges: Vec<Range>,
+    /// Minimum and maximum RDB
+    pub(super) rdb: (f64, f64),
+    /// Odd-electron ion: integer RDB and an odd number of nitrogens for an
+    /// odd nominal mass
+    pub(super) nitrogen_rule: bool,
+    /// Senior rules: the sum of the valences is at least twice the maximum
+    /// valence and at least twice the number of atoms minus one
+    pub(super) senior: bool,
+    /// Maximum number of candidates
+    pub(super) count: usize,
+}
+
+impl Composition {
+    /// Candidates within the constraints (unranked)
+    pub(super) fn candidates(&self) -> Vec<Candidate> {
+        let mut elements = Vec::new();
+        let mut hydrogen = None;
+        for range in &self.ranges {
+            let Some(element) = Element::find(range.symbol.trim()) else {
+                continue;
+            };
+            if element.most_abundant().is_none() || element.valences.is_empty() {
+                continue;
+            }
+            if element == H {
+                hydrogen = Some(range.min..=range.max);
+            } else {
+                elements.push((element, range.min, range.max));
+            }
+        }
+        let mut candidates = Vec::new();
+        let mut counts = vec![0; elements.len()];
+        self.enumerate(&elements, &hydrogen, &mut counts, 0, &mut candidates);
+        candidates
+    }
+
+    fn enumerate(
+        &self,
+        elements: &[(&'static Element, usize, usize)],
+        hydrogen: &Option<RangeInclusive<usize>>,
+        counts: &mut [usize],
+        index: usize,
+        candidates: &mut Vec<Candidate>,
+    ) {
+        let mass = |counts: &[usize]| {
+            elements
+                .iter()
+                .zip(counts)
+                .map(|((element, ..), &count)| self.mass_of(element) * count as f64)
+                .sum::<f64>()
+        };
+        let Some(&(_, min, max)) = elements.get(index) else {
+            self.complete(elements, hydrogen, counts, candidates);
+            return;
+        };
+        for count in min..=max {
+            counts[index] = count;
+            if mass(counts) > self.target() + self.tolerance() {
+                break;
+            }
+            self.enumerate(elements, hydrogen, counts, index + 1, candidates);
+        }
+        counts[index] = 0;
+    }
+
+    /// Candidate of the counts with hydrogen solved from the mass
+    fn complete(
+        &self,
+        elements: &[(&'static Element, usize, usize)],
+        hydrogen: &Option<RangeInclusive<usize>>,
+        counts: &[usize],
+        candidates: &mut Vec<Candidate>,
+    ) {
+        let mut atoms = elements
+            .iter()
+            .zip(counts)
+            .filter(|(_, &count)| count > 0)
+            .map(|(&(element, ..), &count)| (element, count))
+            .collect::<Vec<_>>();
+        let rest = atoms
+            .iter()
+            .map(|&(element, count)| self.mass_of(element) * count as f64)
+            .sum::<f64>();
+        if let Some(hydrogen) = hydrogen {
+            let count = ((self.target() - rest) / self.mass_of(H)).round();
+            if count < 0.0 || !hydrogen.contains(&(count as usize)) {
+                return;
+            }
+            if count > 0.0 {
+                atoms.push((H, count as usize));
+            }
+        }
+        if atoms.is_empty() {
+            return;
+        }
+        let total = atoms
+            .iter()
+            .map(|&(element, count)| self.mass_of(element) * count as f64)
+            .sum::<f64>();
+        let error = match self.ppm {
+            Some(ppm) => {
+                let error = (total - self.target()) / self.target() * 1e6;
+                if error.abs() > ppm {
+                    return;
+                }
+                error
+            }
+            None => {
+                if total != self.target() {
+                    return;
+                }
+                0.0
+            }
+        };
+        let valence = |element: &Element| element.valences[0] as f64;
+        let rdb = 1.0
+            + atoms
+                .iter()
+                .map(|&(element, count)| count as f64 * (valence(element) - 2.0) / 2.0)
+                .sum::<f64>();
+        if rdb < self.rdb.0 || rdb > self.rdb.1 {
+            return;
+        }
+        if self.nitrogen_rule {
+            let nitrogens = atoms
+                .iter()
+                .find(|(element, _)| element.symbol == "N")
+                .map_or(0, |&(_, count)| count);
+            let nominal = self.mass.round() as usize;
+            if rdb.fract() != 0.0 || nominal % 2 != nitrogens % 2 {
+                return;
+            }
+        }
+        if self.senior {
+            let sum = atoms
+                .iter()
+                .map(|&(element, count)| valence(element) * count as f64)
+                .sum::<f64>();
+            let max = atoms
+                .iter()
+                .map(|&(element, _)| valence(element))
+                .fold(0.0, f64::max);
+            let number = atoms.iter().map(|&(_, count)| count).sum::<usize>() as f64;
+            if sum % 2.0 != 0.0 || sum < 2.0 * max || sum < 2.0 * (number - 1.0) {
+                return;
+            }
+        }
+        let formula = Formula {
+            atoms: atoms
+                .into_iter()
+                .map(|(element, count)| {
+                    let atom = Atom {
+                        element,
+                        mass_number: None,
+                    };
+                    (atom, count)
+                })
+                .collect(),
+            charge: 0,
+        };
+        candidates.push(Candidate {
+            formula,
+            rdb,
+            error,
+            fit: Fit::default(),
+        });
+    }
+
+    /// Neutral mass of the ion (nominal if no tolerance)
+    fn target(&self) -> f64 {
+        match self.ppm {
+            Some(_) => self.mass + ELECTRON,
+            None => self.mass.round(),
+        }
+    }
+
+    fn tolerance(&self) -> f64 {
+        self.ppm.map_or(0.0, |ppm| self.target() * ppm / 1e6)
+    }
+
+    fn mass_of(&self, element: &Element) -> f64 {
+        match self.ppm {
+            Some(_) => element.monoisotopic().unwrap_or_default(),
+            None => element.nominal().unwrap_or_default() as f64,
+        }
+    }
+}
+
+impl Default for Composition {
+    fn default() -> Self {
+        Self {
+            show: false,
+            mass: 0.0,
+            ppm: None,
+            ranges: vec![
+                Range::new("C", 1, 40),
+                Range::new("H", 0, 82),
+                Range::new("N", 0, 4),
+                Range::new("O", 0, 6),
+                Range::new("S", 0, 2),
+                Range::new("Cl", 0, 2),
+                Range::new("Br", 0, 2),
+                Range::new("Si", 0, 0),
+                Range::new("P", 0, 0),
+                Range::new("F", 0, 0),
+            ],
+            rdb: (0.0, 40.0),
+            nitrogen_rule: true,
+            senior: true,
+            count: 100,
+        }
+    }
+}
+
+impl Hash for Composition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.mass.to_bits().hash(state);
+        self.ppm.map(f64::to_bits).hash(state);
+        self.ranges.hash(state);
+        self.rdb.0.to_bits().hash(state);
+        self.rdb.1.to_bits().hash(state);
+        self.nitrogen_rule.hash(state);
+        self.senior.hash(state);
+        self.count.hash(state);
+    }
+}
+
+/// Element range
+#[derive(Clone, Debug, Default, Deserialize, Hash, PartialEq, Serialize)]
+pub(super) struct Range {
+    pub(super) symbol: String,
+    pub(super) min: usize,
+    pub(super) max: usize,
+}
+
+impl Range {
+    fn new(symbol: &str, min: usize, max: usize) -> Self {
+        Self {
+            symbol: symbol.to_owned(),
+            min,
+            max,
+        }
+    }
+}
+
+/// Candidate formula
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Candidate {
+    pub(super) formula: Formula,
+    pub(super) rdb: f64,
+    /// Mass error in ppm (zero for a nominal mass)
+    pub(super) error: f64,
+    /// Isotope pattern fit to the observed peaks
+    pub(super) fit: Fit,
+}
+
+fn ordered(value: f64) -> i64 {
+    (value * 1e6) as i64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn formulas(composition: &Composition) -> Vec<String> {
+        composition
+            .candidates()
+            .into_iter()
+            .map(|candidate| candidate.formula.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn nominal() {
+        let composition = Composition {
+            mass: 78.0,
+            ..Default::default()
+        };
+        let formulas = formulas(&composition);
+        assert!(formulas.contains(&"C6H6".to_owned()));
+        // Odd number of nitrogens for an even mass
+        assert!(!formulas.contains(&"C5H4N".to_owned()));
+    }
+
+    #[test]
+    fn accurate() {
+        let composition = Composition {
+            mass: 305.235479 - ELECTRON,
+            ppm: Some(2.0),
+            ..Default::default()
+        };
+        let candidates = composition.candidates();
+        assert!(candidates
+            .iter()
+            .any(|candidate| candidate.formula.to_string() == "C19H31NO2"));
+        assert!(candidates
+            .iter()
+            .all(|candidate| candidate.error.abs() <= 2.0));
+        let candidate = candidates
+            .iter()
+            .find(|candidate| candidate.formula.to_string() == "C19H31NO2")
+            .unwrap();
+        assert_eq!(candidate.rdb, 5.0);
+    }
+}
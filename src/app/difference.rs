@@ -0,0 +1,95 @@
+//! Pairwise mass differences of the peaks, weighted by the product of the
+//! relative intensities of both peaks.
+
+use egui::util::cache::{ComputerMut, FrameCache};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Differenced
+pub(super) type Differenced = FrameCache<BTreeMap<usize, f64>, Differencer>;
+
+/// Differencer
+#[derive(Default)]
+pub(super) struct Differencer;
+
+impl ComputerMut<(&BTreeMap<usize, u64>, usize), BTreeMap<usize, f64>> for Differencer {
+    fn compute(&mut self, (peaks, max): (&BTreeMap<usize, u64>, usize)) -> BTreeMap<usize, f64> {
+        histogram(peaks, max)
+    }
+}
+
+/// Differences
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub(super) struct Differences {
+    pub(super) show: bool,
+    /// Maximum delta of the histogram
+    pub(super) max: usize,
+    /// Number of the most intense peaks in the matrix
+    pub(super) top: usize,
+}
+
+impl Default for Differences {
+    fn default() -> Self {
+        Self {
+            show: false,
+            max: 100,
+            top: 30,
+        }
+    }
+}
+
+/// Summed weights of the deltas up to `max`
+pub(super) fn histogram(peaks: &BTreeMap<usize, u64>, max: usize) -> BTreeMap<usize, f64> {
+    let relative = relative(peaks);
+    let mut histogram = BTreeMap::new();
+    for (index, &(lighter, lhs)) in relative.iter().enumerate() {
+        for &(heavier, rhs) in &relative[index + 1..] {
+            let delta = heavier - lighter;
+            if delta > max {
+                break;
+            }
+            *histogram.entry(delta).or_default() += lhs * rhs;
+        }
+    }
+    histogram
+}
+
+/// Masses of the `top` most intense peaks (ascending) and the weights of their
+/// pairs
+pub(super) fn matrix(peaks: &BTreeMap<usize, u64>, top: usize) -> (Vec<usize>, Vec<Vec<f64>>) {
+    let mut relative = relative(peaks);
+    relative.sort_by(|lhs, rhs| rhs.1.total_cmp(&lhs.1));
+    relative.truncate(top);
+    relative.sort_by_key(|&(mass, _)| mass);
+    let masses = relative.iter().map(|&(mass, _)| mass).collect();
+    let weights = relative
+        .iter()
+        .map(|&(_, lhs)| relative.iter().map(|&(_, rhs)| lhs * rhs).collect())
+        .collect();
+    (masses, weights)
+}
+
+/// Masses and intensities relative to the base peak
+fn relative(peaks: &BTreeMap<usize, u64>) -> Vec<(usize, f64)> {
+    let base = peaks.values().copied().max().unwrap_or_default().max(1) as f64;
+    peaks
+        .iter()
+        .map(|(&mass, &intensity)| (mass, intensity as f64 / base))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn differences() {
+        let peaks = BTreeMap::from([(100, 100), (114, 50), (128, 50), (300, 10)]);
+        let histogram = histogram(&peaks, 50);
+        assert_eq!(histogram, BTreeMap::from([(14, 0.5 + 0.25), (28, 0.5)]));
+        let (masses, weights) = matrix(&peaks, 2);
+        assert_eq!(masses, [100, 114]);
+        assert_eq!(weights, [[1.0, 0.5], [0.5, 0.25]]);
+    }
+}
@@ -0,0 +1,124 @@
+use super::predictioner::Prediction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Neighbourhood of a step peak in Da
+const NEIGHBOURHOOD: usize = 2;
+
+/// Explanation
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(super) struct Explanation {
+    pub(super) show: bool,
+    /// Rank of the explained prediction
+    pub(super) rank: usize,
+    /// Rank of the compared prediction
+    pub(super) compare: Option<usize>,
+}
+
+/// Evidence of a step
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Evidence {
+    pub(super) mass: usize,
+    pub(super) delta: usize,
+    /// Intensity of the matched peak (`None` if the step is a zero-filled miss)
+    pub(super) intensity: Option<u64>,
+    /// Intensity in percent of the base peak
+    pub(super) relative: f64,
+    /// Peaks within the neighbourhood of the step mass
+    pub(super) neighbours: Vec<(usize, u64)>,
+    /// Score term of the step
+    pub(super) contribution: f64,
+}
+
+impl Evidence {
+    pub(super) fn collect(prediction: &Prediction, peaks: &BTreeMap<usize, u64>) -> Vec<Self> {
+        let base = peaks.values().copied().max().unwrap_or_default();
+        prediction
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let intensity = step.intensity.unwrap_or_default();
+                let range = step.mass.saturating_sub(NEIGHBOURHOOD)..=step.mass + NEIGHBOURHOOD;
+                Self {
+                    mass: step.mass,
+                    delta: step.delta,
+                    intensity: step.intensity,
+                    relative: if base == 0 {
+                        0.0
+                    } else {
+                        intensity as f64 / base as f64 * 100.0
+                    },
+                    neighbours: peaks
+                        .range(range)
+                        .filter(|(&mass, _)| mass != step.mass)
+                        .map(|(&mass, &intensity)| (mass, intensity))
+                        .collect(),
+                    contribution: prediction
+                        .score
+                        .terms
+                        .get(index)
+                        .copied()
+                        .unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Steps where the paths of two predictions diverge (different masses or a
+/// step only one of them has)
+pub(super) fn divergence(lhs: &Prediction, rhs: &Prediction) -> Vec<bool> {
+    let length = lhs.steps.len().max(rhs.steps.len());
+    (0..length)
+        .map(|index| {
+            let mass = |prediction: &Prediction| prediction.steps.get(index).map(|step| step.mass);
+            mass(lhs) != mass(rhs)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::{predictioner::Step, scoring::Scoring};
+
+    fn prediction(steps: &[(usize, Option<u64>)]) -> Prediction {
+        let intensities = steps
+            .iter()
+            .map(|&(_, intensity)| intensity)
+            .collect::<Vec<_>>();
+        Prediction {
+            anchor: 300,
+            steps: steps
+                .iter()
+                .map(|&(mass, intensity)| Step {
+                    mass,
+                    delta: 14,
+                    intensity,
+                    feature: None,
+                })
+                .collect(),
+            score: Scoring::Sum.score(&intensities, 200, 0),
+        }
+    }
+
+    #[test]
+    fn evidence() {
+        let peaks = BTreeMap::from([(284, 10), (285, 5), (286, 50), (290, 200)]);
+        let evidence = Evidence::collect(&prediction(&[(286, Some(50)), (272, None)]), &peaks);
+        assert_eq!(evidence[0].relative, 25.0);
+        assert_eq!(evidence[0].neighbours, [(284, 10), (285, 5)]);
+        assert_eq!(evidence[0].contribution, 50.0);
+        assert_eq!(evidence[1].intensity, None);
+        assert_eq!(evidence[1].contribution, 0.0);
+    }
+
+    #[test]
+    fn diverge() {
+        let lhs = prediction(&[(286, Some(50)), (272, None), (258, None)]);
+        let rhs = prediction(&[(286, Some(50)), (274, None)]);
+        assert_eq!(divergence(&lhs, &rhs), [false, true, true]);
+    }
+}
@@ -0,0 +1,106 @@
+//! Structural features of a chain and their gap signatures in the ladder.
+//!
+//! A plain chain loses 14 (CH2) per carbon. A feature replaces one such step
+//! with the gaps of its unit, because the ions inside the unit are suppressed.
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// Feature
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(super) enum Feature {
+    /// Methyl branch (iso, anteiso or mid-chain): CH(CH3)
+    MethylBranch,
+    /// Cyclopropane ring: CH-CH2-CH
+    Cyclopropane,
+    /// Cyclopentenyl ring: C5H6
+    Cyclopentenyl,
+    /// Hydroxyl group: CH(OH)
+    Hydroxyl,
+    /// Oxo group: C=O
+    Oxo,
+}
+
+impl Feature {
+    pub(super) const ALL: [Self; 5] = [
+        Self::MethylBranch,
+        Self::Cyclopropane,
+        Self::Cyclopentenyl,
+        Self::Hydroxyl,
+        Self::Oxo,
+    ];
+
+    /// Gaps replacing a single 14 step of the ladder
+    pub(super) fn signature(&self) -> &'static [usize] {
+        match self {
+            Self::MethylBranch => &[28],
+            Self::Cyclopropane => &[40],
+            Self::Cyclopentenyl => &[66],
+            Self::Hydroxyl => &[30],
+            Self::Oxo => &[28],
+        }
+    }
+
+    /// Name with the other features of the same signature, which the gaps
+    /// can't tell apart (a 28 gap is a methyl branch or an oxo group)
+    pub(super) fn label(&self) -> String {
+        Self::ALL
+            .iter()
+            .filter(|other| other.signature() == self.signature())
+            .join(" / ")
+    }
+
+    /// Patterns with the step at each index replaced by the signature (the
+    /// signature steps are marked)
+    pub(super) fn patterns(&self, pattern: &[Vec<usize>]) -> Vec<(Vec<Vec<usize>>, Vec<bool>)> {
+        let signature = self.signature();
+        (0..pattern.len())
+            .map(|index| {
+                let mut features = vec![false; index];
+                let mut steps = pattern[..index].to_vec();
+                for &delta in signature {
+                    steps.push(vec![delta]);
+                    features.push(true);
+                }
+                for step in &pattern[index + 1..] {
+                    steps.push(step.clone());
+                    features.push(false);
+                }
+                (steps, features)
+            })
+            .collect()
+    }
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::MethylBranch => "Methyl branch",
+            Self::Cyclopropane => "Cyclopropane",
+            Self::Cyclopentenyl => "Cyclopentenyl",
+            Self::Hydroxyl => "Hydroxyl",
+            Self::Oxo => "Oxo",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn patterns() {
+        let pattern = [vec![15], vec![12, 14]];
+        assert_eq!(
+            Feature::Hydroxyl.patterns(&pattern),
+            [
+                (vec![vec![30], vec![12, 14]], vec![true, false]),
+                (vec![vec![15], vec![30]], vec![false, true]),
+            ],
+        );
+        assert_eq!(Feature::Oxo.label(), "Methyl branch / Oxo");
+        assert_eq!(Feature::MethylBranch.label(), Feature::Oxo.label());
+        assert_eq!(Feature::Hydroxyl.label(), "Hydroxyl");
+    }
+}
@@ -0,0 +1,424 @@
+//! In-silico fragmentation of a structure under electron ionization.
+//!
+//! The molecular ion breaks its acyclic single bonds between heavy atoms (up
+//! to `depth` times in a row). The pieces next to a heteroatom are favored
+//! (α-cleavage), the hydrogen rearrangements shift a piece by one hydrogen,
+//! and the McLafferty rearrangement moves a γ-hydrogen to a double bonded
+//! heteroatom while the α-β bond breaks. The rules only weigh the fragments:
+//! the predicted intensities are relative, not kinetic.
+
+use crate::{
+    formula::{Atom, Formula},
+    parser::Parsed,
+    widget::{
+        atom::{Element, C, H},
+        molecule::Molecule,
+    },
+};
+use egui::util::cache::{ComputerMut, FrameCache};
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
+};
+
+/// Intensity of the base peak of a predicted spectrum
+const BASE: f64 = 999.0;
+
+/// Fragmented
+pub(super) type Fragmented = FrameCache<Vec<Fragment>, Fragmenter>;
+
+/// Fragmenter
+#[derive(Default)]
+pub(super) struct Fragmenter;
+
+impl ComputerMut<&Fragmentation, Vec<Fragment>> for Fragmenter {
+    fn compute(&mut self, fragmentation: &Fragmentation) -> Vec<Fragment> {
+        match fragmentation.smiles.parse::<Molecule>() {
+            Ok(molecule) => fragmentation.fragments(&molecule),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Fragmentation settings
+#[derive(Clone, Debug, Deserialize, Hash, PartialEq, Serialize)]
+#[serde(default)]
+pub(super) struct Fragmentation {
+    pub(super) show: bool,
+    /// Structure (SMILES)
+    pub(super) smiles: String,
+    /// Maximum number of consecutive cleavages
+    pub(super) depth: usize,
+    pub(super) hydrogen_rearrangements: bool,
+    pub(super) mclafferty: bool,
+    pub(super) alpha_cleavage: bool,
+}
+
+impl Fragmentation {
+    /// Fragments (the most favored one of each mass first)
+    pub(super) fn fragments(&self, molecule: &Molecule) -> Vec<Fragment> {
+        let all = molecule.node_indices().collect::<BTreeSet<_>>();
+        let mut pieces = HashMap::from([((all.clone(), 0), (Rule::MolecularIon, 0, 1.0))]);
+        let mut current = vec![all];
+        for depth in 1..=self.depth {
+            let mut next = Vec::new();
+            for atoms in &current {
+                for (piece, shift, rule) in self.cleavages(molecule, atoms) {
+                    let weight = rule.weight() / 2f64.powi(depth as i32 - 1);
+                    let key = (piece.clone(), shift);
+                    match pieces.get(&key) {
+                        Some(&(.., known)) if known >= weight => continue,
+                        _ => {}
+                    }
+                    if shift == 0 && !pieces.contains_key(&key) {
+                        next.push(piece);
+                    }
+                    pieces.insert(key, (rule, depth, weight));
+                }
+            }
+            current = next;
+        }
+        let mut fragments = pieces
+            .into_iter()
+            .map(|((atoms, shift), (rule, depth, weight))| Fragment {
+                mass: nominal(molecule, &atoms, shift),
+                formula: formula(molecule, &atoms, shift),
+                rule,
+                depth,
+                weight,
+            })
+            .collect::<Vec<_>>();
+        fragments.sort_by(|lhs, rhs| {
+            rhs.mass
+                .cmp(&lhs.mass)
+                .then(rhs.weight.total_cmp(&lhs.weight))
+                .then(lhs.formula.to_string().cmp(&rhs.formula.to_string()))
+        });
+        fragments
+    }
+
+    /// Pieces of the fragment (its atoms, the hydrogen shift and the rule)
+    fn cleavages(
+        &self,
+        molecule: &Molecule,
+        atoms: &BTreeSet<NodeIndex>,
+    ) -> Vec<(BTreeSet<NodeIndex>, i8, Rule)> {
+        let mut cleavages = Vec::new();
+        for edge in molecule.edge_references() {
+            let (source, target) = (edge.source(), edge.target());
+            if *edge.weight() != 1
+                || molecule[source] == H
+                || molecule[target] == H
+                || !atoms.contains(&source)
+                || !atoms.contains(&target)
+            {
+                continue;
+            }
+            let Some((lhs, rhs)) = split(molecule, atoms, source, target) else {
+                continue;
+            };
+            for (piece, atom) in [(lhs, source), (rhs, target)] {
+                let rule = match self.alpha_cleavage && is_alpha(molecule, &piece, atom) {
+                    true => Rule::AlphaCleavage,
+                    false => Rule::Cleavage,
+                };
+                if self.hydrogen_rearrangements {
+                    cleavages.push((piece.clone(), 1, Rule::Rearrangement));
+                    if piece.iter().any(|&atom| molecule[atom] == H) {
+                        cleavages.push((piece.clone(), -1, Rule::Rearrangement));
+                    }
+                }
+                cleavages.push((piece, 0, rule));
+            }
+        }
+        if self.mclafferty {
+            cleavages.extend(
+                mclafferty(molecule, atoms)
+                    .into_iter()
+                    .map(|piece| (piece, 1, Rule::McLafferty)),
+            );
+        }
+        cleavages
+    }
+}
+
+impl Default for Fragmentation {
+    fn default() -> Self {
+        Self {
+            show: false,
+            smiles: String::new(),
+            depth: 2,
+            hydrogen_rearrangements: true,
+            mclafferty: true,
+            alpha_cleavage: true,
+        }
+    }
+}
+
+/// Fragment
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Fragment {
+    pub(super) mass: usize,
+    pub(super) formula: Formula,
+    pub(super) rule: Rule,
+    /// Number of the cleavages from the molecular ion
+    pub(super) depth: usize,
+    /// Relative intensity weight
+    pub(super) weight: f64,
+}
+
+/// Fragmentation rule
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(super) enum Rule {
+    MolecularIon,
+    Cleavage,
+    AlphaCleavage,
+    Rearrangement,
+    McLafferty,
+}
+
+impl Rule {
+    fn weight(&self) -> f64 {
+        match self {
+            Self::MolecularIon | Self::Cleavage => 1.0,
+            Self::AlphaCleavage | Self::McLafferty => 3.0,
+            Self::Rearrangement => 0.25,
+        }
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::MolecularIon => f.write_str("Molecular ion"),
+            Self::Cleavage => f.write_str("Cleavage"),
+            Self::AlphaCleavage => f.write_str("α-cleavage"),
+            Self::Rearrangement => f.write_str("H rearrangement"),
+            Self::McLafferty => f.write_str("McLafferty"),
+        }
+    }
+}
+
+/// Predicted spectrum (the weights summed by mass, the base peak at 999)
+pub(super) fn spectrum(name: &str, fragments: &[Fragment]) -> Parsed {
+    let mut weights = BTreeMap::<usize, f64>::new();
+    for fragment in fragments {
+        *weights.entry(fragment.mass).or_default() += fragment.weight;
+    }
+    let max = weights.values().copied().fold(0.0, f64::max);
+    let molecular_ion = fragments
+        .iter()
+        .find(|fragment| fragment.rule == Rule::MolecularIon);
+    Parsed {
+        name: name.to_owned(),
+        formula: molecular_ion.map_or_else(String::new, |fragment| fragment.formula.to_string()),
+        mw: molecular_ion.map(|fragment| fragment.mass as _),
+        peaks: weights
+            .into_iter()
+            .map(|(mass, weight)| (mass, (weight / max * BASE).round().max(1.0) as _))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Weighted cosine similarity of two spectra (the masses times the square
+/// roots of the intensities, as in the library searches)
+pub(super) fn similarity(lhs: &BTreeMap<usize, u64>, rhs: &BTreeMap<usize, u64>) -> f64 {
+    let weighted = |mass: usize, intensity: u64| mass as f64 * (intensity as f64).sqrt();
+    let norm = |peaks: &BTreeMap<usize, u64>| {
+        peaks
+            .iter()
+            .map(|(&mass, &intensity)| weighted(mass, intensity).powi(2))
+            .sum::<f64>()
+            .sqrt()
+    };
+    let product = lhs
+        .iter()
+        .filter_map(|(&mass, &intensity)| {
+            let other = rhs.get(&mass)?;
+            Some(weighted(mass, intensity) * weighted(mass, *other))
+        })
+        .sum::<f64>();
+    let norms = norm(lhs) * norm(rhs);
+    if norms == 0.0 {
+        return 0.0;
+    }
+    product / norms
+}
+
+/// Pieces of the fragment broken at the bond (`None` for a ring bond)
+fn split(
+    molecule: &Molecule,
+    atoms: &BTreeSet<NodeIndex>,
+    source: NodeIndex,
+    target: NodeIndex,
+) -> Option<(BTreeSet<NodeIndex>, BTreeSet<NodeIndex>)> {
+    let mut piece = BTreeSet::from([source]);
+    let mut stack = vec![source];
+    while let Some(atom) = stack.pop() {
+        for neighbor in molecule.neighbors(atom) {
+            if atom == source && neighbor == target {
+                continue;
+            }
+            if atoms.contains(&neighbor) && piece.insert(neighbor) {
+                stack.push(neighbor);
+            }
+        }
+    }
+    if piece.contains(&target) {
+        return None;
+    }
+    let rest = atoms.difference(&piece).copied().collect();
+    Some((piece, rest))
+}
+
+/// The piece keeps the heteroatom next to its broken atom (amines, ethers,
+/// alcohols and the acylium ions of the carbonyls)
+fn is_alpha(molecule: &Molecule, piece: &BTreeSet<NodeIndex>, atom: NodeIndex) -> bool {
+    molecule[atom] == C
+        && molecule
+            .neighbors(atom)
+            .any(|neighbor| piece.contains(&neighbor) && is_heteroatom(molecule[neighbor]))
+}
+
+fn is_heteroatom(element: &Element) -> bool {
+    matches!(element.symbol, "N" | "O" | "S")
+}
+
+/// Pieces of the McLafferty rearrangement: X=C-Cα-Cβ-Cγ-H, the α-β bond
+/// broken, the piece with the heteroatom gaining the γ-hydrogen
+fn mclafferty(molecule: &Molecule, atoms: &BTreeSet<NodeIndex>) -> Vec<BTreeSet<NodeIndex>> {
+    let heavy = |atom: NodeIndex| atoms.contains(&atom) && molecule[atom] != H;
+    let mut pieces = Vec::new();
+    for double in molecule.edge_references() {
+        if *double.weight() != 2 {
+            continue;
+        }
+        let (heteroatom, carbon) = match (double.source(), double.target()) {
+            (lhs, rhs) if is_heteroatom(molecule[lhs]) => (lhs, rhs),
+            (lhs, rhs) if is_heteroatom(molecule[rhs]) => (rhs, lhs),
+            _ => continue,
+        };
+        if !atoms.contains(&heteroatom) || !heavy(carbon) {
+            continue;
+        }
+        for alpha in molecule.neighbors(carbon).filter(|&atom| heavy(atom)) {
+            for beta in molecule.neighbors(alpha).filter(|&atom| heavy(atom)) {
+                if beta == carbon {
+                    continue;
+                }
+                let gamma = molecule.neighbors(beta).any(|gamma| {
+                    heavy(gamma)
+                        && gamma != alpha
+                        && molecule
+                            .neighbors(gamma)
+                            .any(|atom| atoms.contains(&atom) && molecule[atom] == H)
+                });
+                if !gamma {
+                    continue;
+                }
+                let bond = molecule.find_edge(alpha, beta).map(|edge| molecule[edge]);
+                if bond != Some(1) {
+                    continue;
+                }
+                if let Some((piece, _)) = split(molecule, atoms, alpha, beta) {
+                    pieces.push(piece);
+                }
+            }
+        }
+    }
+    pieces
+}
+
+fn nominal(molecule: &Molecule, atoms: &BTreeSet<NodeIndex>, shift: i8) -> usize {
+    let mass = atoms
+        .iter()
+        .map(|&atom| molecule[atom].nominal().unwrap_or_default())
+        .sum::<usize>();
+    mass.saturating_add_signed(shift as _)
+}
+
+fn formula(molecule: &Molecule, atoms: &BTreeSet<NodeIndex>, shift: i8) -> Formula {
+    let mut counts = BTreeMap::<_, usize>::new();
+    for &atom in atoms {
+        *counts.entry(molecule[atom]).or_default() += 1;
+    }
+    let hydrogens = counts.entry(H).or_default();
+    *hydrogens = hydrogens.saturating_add_signed(shift as _);
+    Formula {
+        atoms: counts
+            .into_iter()
+            .filter(|&(_, count)| count > 0)
+            .map(|(element, count)| {
+                let atom = Atom {
+                    element,
+                    mass_number: None,
+                };
+                (atom, count)
+            })
+            .collect(),
+        charge: 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fragments(smiles: &str, fragmentation: Fragmentation) -> Vec<Fragment> {
+        fragmentation.fragments(&smiles.parse().unwrap())
+    }
+
+    #[test]
+    fn cleavages() {
+        let fragmentation = Fragmentation {
+            depth: 1,
+            hydrogen_rearrangements: false,
+            mclafferty: false,
+            ..Default::default()
+        };
+        // Butane: M, C3H7, C2H5, CH3
+        let masses = fragments("CCCC", fragmentation.clone())
+            .into_iter()
+            .map(|fragment| fragment.mass)
+            .collect::<BTreeSet<_>>();
+        assert_eq!(masses, BTreeSet::from([15, 29, 43, 58]));
+        // Cyclohexane keeps its ring
+        assert_eq!(fragments("C1CCCCC1", fragmentation.clone()).len(), 1);
+        // Diethylamine: the iminium ion CH2=NH+Et (58) by α-cleavage
+        let amine = fragments("CCNCC", fragmentation);
+        let alpha = amine
+            .iter()
+            .find(|fragment| fragment.rule == Rule::AlphaCleavage)
+            .unwrap();
+        assert_eq!(
+            (alpha.mass, alpha.formula.to_string()),
+            (58, "C3H8N".to_owned())
+        );
+    }
+
+    #[test]
+    fn mclafferty() {
+        // Butanal: the enol ion C2H4O (44)
+        let fragments = fragments("CCCC=O", Default::default());
+        assert!(fragments
+            .iter()
+            .any(|fragment| fragment.rule == Rule::McLafferty && fragment.mass == 44));
+    }
+
+    #[test]
+    fn spectra() {
+        let fragments = fragments("CCCC=O", Default::default());
+        let predicted = spectrum("Butanal", &fragments);
+        assert_eq!(predicted.mw, Some(72));
+        assert_eq!(predicted.formula, "C4H8O");
+        assert_eq!(predicted.peaks.values().max(), Some(&999));
+        assert!((similarity(&predicted.peaks, &predicted.peaks) - 1.0).abs() < 1e-12);
+        let observed = BTreeMap::from([(44, 999), (72, 500), (29, 200)]);
+        let score = similarity(&observed, &predicted.peaks);
+        assert!(score > 0.0 && score < 1.0);
+        assert_eq!(similarity(&observed, &BTreeMap::new()), 0.0);
+    }
+}
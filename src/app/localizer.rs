@@ -0,0 +1,297 @@
+//! Double bond localization in picolinyl ester/DMOX fatty acid derivatives.
+//!
+//! Going down from the molecular ion the chain loses CH3 (15) and then one
+//! carbon per step: 14 for CH2 and 12 from C(k) to C(k-1) if there is a double
+//! bond between C(k) and C(k+1).
+
+use super::{
+    predictioner::{Anchor, Direction, Key, Prediction, Predictioner},
+    scoring::Scoring,
+};
+use crate::widget::series::MAX_ISOMERS;
+use egui::util::cache::{ComputerMut, FrameCache};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// Chain
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(super) struct Chain {
+    pub(super) carbons: usize,
+    pub(super) bonds: usize,
+}
+
+impl Chain {
+    /// Chain and double bond positions from a file name like `16_1-345`,
+    /// `9,12-18_2` or `9,12_16_2`
+    pub(super) fn from_name(name: &str) -> Option<(Self, Vec<usize>)> {
+        let tokens = name.split(|c: char| !c.is_ascii_digit() && !",_-".contains(c));
+        tokens.rev().find_map(|token| {
+            let parts = token.split('-').collect::<Vec<_>>();
+            (0..parts.len()).rev().find_map(|index| {
+                let numbers = parts[index].split('_').collect::<Vec<_>>();
+                let [prefix @ .., carbons, bonds] = &numbers[..] else {
+                    return None;
+                };
+                let chain = Self {
+                    carbons: carbons.parse().ok()?,
+                    bonds: bonds.parse().ok()?,
+                };
+                // `9,12_16_2` or `9,12-18_2` (but not a `2_18_1` replicate)
+                let positions = match prefix.last() {
+                    Some(positions) => Some(positions).filter(|positions| positions.contains(',')),
+                    None => index.checked_sub(1).map(|index| &parts[index]),
+                };
+                let positions = positions
+                    .and_then(|positions| {
+                        positions
+                            .split(',')
+                            .map(str::parse)
+                            .collect::<Result<Vec<usize>, _>>()
+                            .ok()
+                    })
+                    .filter(|positions| chain.is_valid(positions))
+                    .unwrap_or_default();
+                Some((chain, positions))
+            })
+        })
+    }
+
+    /// All positions of `bonds` non-cumulated double bonds detectable in the
+    /// ladder
+    fn isomers(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        (3..self.carbons)
+            .combinations(self.bonds)
+            .filter(|positions| self.is_valid(positions))
+    }
+
+    /// Number of the isomers
+    fn count(&self) -> f64 {
+        // Non-adjacent positions among the carbons - 3 of the ladder
+        let slots = (self.carbons + 1).saturating_sub(3 + self.bonds);
+        (0..self.bonds).fold(1.0, |count, index| {
+            count * slots.saturating_sub(index) as f64 / (index + 1) as f64
+        })
+    }
+
+    fn is_valid(&self, positions: &[usize]) -> bool {
+        positions.len() == self.bonds
+            && positions
+                .iter()
+                .all(|&position| (2..self.carbons).contains(&position))
+            && positions.windows(2).all(|pair| pair[0] + 1 < pair[1])
+    }
+
+    /// Ladder pattern from the molecular ion down to C2
+    fn pattern(&self, positions: &[usize]) -> Vec<Vec<usize>> {
+        let mut pattern = vec![vec![15]];
+        for carbon in (3..self.carbons).rev() {
+            pattern.push(vec![if positions.contains(&carbon) { 12 } else { 14 }]);
+        }
+        pattern
+    }
+}
+
+impl Display for Chain {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.carbons, self.bonds)
+    }
+}
+
+/// Key
+#[derive(Clone, Copy, Debug, Hash)]
+pub(super) struct LocalizerKey<'a> {
+    pub(super) mass: usize,
+    pub(super) peaks: &'a BTreeMap<usize, u64>,
+    pub(super) chain: Chain,
+    pub(super) scoring: Scoring,
+    pub(super) penalty: u8,
+}
+
+/// Localized
+pub(super) type Localized = FrameCache<Result<Vec<Isomer>, String>, Localizer>;
+
+/// Localizer
+#[derive(Default)]
+pub(super) struct Localizer;
+
+impl ComputerMut<LocalizerKey<'_>, Result<Vec<Isomer>, String>> for Localizer {
+    fn compute(&mut self, args: LocalizerKey) -> Result<Vec<Isomer>, String> {
+        if args.chain.count() > MAX_ISOMERS {
+            return Err(format!("{} has too many isomers", args.chain));
+        }
+        let mut isomers = args
+            .chain
+            .isomers()
+            .filter_map(|positions| {
+                let pattern = args.chain.pattern(&positions);
+                let prediction = Predictioner
+                    .compute(Key {
+                        mass: args.mass,
+                        peaks: args.peaks,
+                        pattern: &pattern,
+                        features: &[],
+                        zero_is_included: true,
+                        scoring: args.scoring,
+                        penalty: args.penalty,
+                        direction: Direction::Backward,
+                        anchor: Anchor::Mass,
+                        threshold: 0,
+                    })
+                    .into_iter()
+                    .next()?;
+                let diagnostics = positions
+                    .iter()
+                    .filter_map(|&position| {
+                        // Fragment with carbons up to C(k) is at step `n - 1 - k`
+                        let index = args.chain.carbons - 1 - position;
+                        let upper = prediction.steps[index];
+                        let lower = *prediction.steps.get(index + 1)?;
+                        Some(Diagnostic {
+                            position,
+                            upper: (upper.mass, upper.intensity),
+                            lower: (lower.mass, lower.intensity),
+                        })
+                    })
+                    .collect();
+                Some(Isomer {
+                    positions,
+                    prediction,
+                    diagnostics,
+                })
+            })
+            .collect::<Vec<_>>();
+        isomers.sort_by(|left, right| {
+            right
+                .prediction
+                .score
+                .value
+                .total_cmp(&left.prediction.score.value)
+        });
+        Ok(isomers)
+    }
+}
+
+/// Isomer
+#[derive(Clone, Debug)]
+pub(super) struct Isomer {
+    pub(super) positions: Vec<usize>,
+    pub(super) prediction: Prediction,
+    pub(super) diagnostics: Vec<Diagnostic>,
+}
+
+impl Display for Isomer {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Δ{}", self.positions.iter().join(","))
+    }
+}
+
+/// Diagnostic ions of a double bond: the 12 gap between the fragments ending
+/// at C(k-1) (lower) and C(k) (upper)
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Diagnostic {
+    pub(super) position: usize,
+    pub(super) upper: (usize, Option<u64>),
+    pub(super) lower: (usize, Option<u64>),
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let intensity = |intensity: Option<u64>| {
+            intensity.map_or("missing".to_owned(), |intensity| intensity.to_string())
+        };
+        write!(
+            f,
+            "Δ{}: gap of 12 between {} ({}) and {} ({})",
+            self.position,
+            self.lower.0,
+            intensity(self.lower.1),
+            self.upper.0,
+            intensity(self.upper.1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_name() {
+        let chain = |carbons, bonds| Chain { carbons, bonds };
+        assert_eq!(
+            Chain::from_name("16_1-345.MSP"),
+            Some((chain(16, 1), vec![]))
+        );
+        assert_eq!(
+            Chain::from_name("input/1/9,12-18_2.MSP"),
+            Some((chain(18, 2), vec![9, 12])),
+        );
+        assert_eq!(
+            Chain::from_name("9,12_16_2.MSP"),
+            Some((chain(16, 2), vec![9, 12])),
+        );
+        assert_eq!(Chain::from_name("1-18_1.MSP"), Some((chain(18, 1), vec![])));
+        assert_eq!(Chain::from_name("2_18_1.MSP"), Some((chain(18, 1), vec![])));
+        assert_eq!(
+            Chain::from_name("16_1-345 (51,316).MSP"),
+            Some((chain(16, 1), vec![])),
+        );
+        assert_eq!(Chain::from_name("19.MSP"), None);
+    }
+
+    #[test]
+    fn isomers() {
+        let chain = Chain {
+            carbons: 8,
+            bonds: 2,
+        };
+        assert_eq!(
+            chain.isomers().collect::<Vec<_>>(),
+            [
+                vec![3, 5],
+                vec![3, 6],
+                vec![3, 7],
+                vec![4, 6],
+                vec![4, 7],
+                vec![5, 7]
+            ],
+        );
+        assert_eq!(chain.count(), 6.0);
+        let chain = Chain {
+            carbons: 40,
+            bonds: 6,
+        };
+        assert!(chain.count() > MAX_ISOMERS);
+    }
+
+    #[test]
+    fn localize() {
+        // Picolinyl 9-hexadecenoate (M = 345): 12 gap between C8 (234) and C9 (246)
+        let chain = Chain {
+            carbons: 16,
+            bonds: 1,
+        };
+        let mut mass = 345 - 15;
+        let mut peaks = BTreeMap::from([(345, 50), (mass, 20)]);
+        for carbon in (3..16).rev() {
+            mass -= if carbon == 9 { 12 } else { 14 };
+            peaks.insert(mass, 20);
+        }
+        let isomers = Localizer
+            .compute(LocalizerKey {
+                mass: 345,
+                peaks: &peaks,
+                chain,
+                scoring: Scoring::Count,
+                penalty: 0,
+            })
+            .unwrap();
+        assert_eq!(isomers[0].positions, [9]);
+        assert_eq!(isomers[0].diagnostics[0].lower.0, 234);
+        assert_eq!(isomers[0].diagnostics[0].upper.0, 246);
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Neutral losses
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub(super) struct Losses {
+    pub(super) show: bool,
+    /// Precursor mass (the Finder mass if `None`)
+    pub(super) precursor: Option<usize>,
+    /// Known neutral losses
+    pub(super) dictionary: Vec<Loss>,
+    /// Highlight the precursor-fragment pairs of the known losses on the plot
+    pub(super) highlight: bool,
+}
+
+impl Losses {
+    /// Fragments (peaks lighter than the precursor) from the smallest loss
+    pub(super) fn fragments(
+        &self,
+        precursor: usize,
+        peaks: &BTreeMap<usize, u64>,
+    ) -> Vec<Fragment> {
+        peaks
+            .range(..precursor)
+            .rev()
+            .map(|(&mass, &intensity)| {
+                let loss = precursor - mass;
+                Fragment {
+                    mass,
+                    intensity,
+                    loss,
+                    known: self
+                        .dictionary
+                        .iter()
+                        .filter(|known| known.mass == loss)
+                        .map(|known| known.name.clone())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Losses {
+    fn default() -> Self {
+        Self {
+            show: false,
+            precursor: None,
+            dictionary: [
+                (1, "H•"),
+                (2, "H2"),
+                (15, "CH3•"),
+                (17, "OH•"),
+                (18, "H2O"),
+                (28, "CO, C2H4"),
+                (29, "CHO•, C2H5•"),
+                (31, "OCH3•"),
+                (32, "CH3OH"),
+                (35, "Cl•"),
+                (36, "HCl"),
+                (42, "CH2CO"),
+                (43, "CH3CO•, C3H7•"),
+                (44, "CO2"),
+                (45, "COOH•, OC2H5•"),
+                (46, "C2H5OH, NO2"),
+                (59, "COOCH3•"),
+                (60, "CH3COOH"),
+                (73, "(CH3)3Si•"),
+                (79, "Br•"),
+                (90, "(CH3)3SiOH"),
+            ]
+            .into_iter()
+            .map(|(mass, name)| Loss {
+                mass,
+                name: name.to_owned(),
+            })
+            .collect(),
+            highlight: true,
+        }
+    }
+}
+
+/// Known neutral loss
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub(super) struct Loss {
+    pub(super) mass: usize,
+    pub(super) name: String,
+}
+
+/// Fragment of the precursor
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Fragment {
+    pub(super) mass: usize,
+    pub(super) intensity: u64,
+    pub(super) loss: usize,
+    /// Names of the known losses of the mass
+    pub(super) known: Vec<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fragments() {
+        let peaks = BTreeMap::from([(74, 999), (255, 40), (264, 30), (267, 20), (296, 60)]);
+        let fragments = Losses::default().fragments(296, &peaks);
+        let losses = fragments
+            .iter()
+            .map(|fragment| (fragment.mass, fragment.loss, fragment.known.join("; ")))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            losses,
+            [
+                (267, 29, "CHO•, C2H5•".to_owned()),
+                (264, 32, "CH3OH".to_owned()),
+                (255, 41, String::new()),
+                (74, 222, String::new()),
+            ]
+        );
+    }
+}
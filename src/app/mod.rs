@@ -1,10 +1,32 @@
 use self::{
     bounder::Bounded,
-    predictioner::{Key, Predicted},
+    cluster::{Cluster, Clustered},
+    composer::{Candidate, Composed, ComposerKey, Composition},
+    difference::{matrix, Differenced, Differences},
+    explanation::{Evidence, Explanation},
+    feature::Feature,
+    fragmenter::{similarity, spectrum, Fragmentation, Fragmented},
+    localizer::{Chain, Isomer, Localized, LocalizerKey},
+    loss::Losses,
+    molecular_ion::MolecularIon,
+    patterner::{Isotopes, Patterned},
+    predictioner::{Anchor, Direction, Key, Predicted, Prediction},
+    ranker::{Ranked, RankerKey, Ranking, Structure},
+    scoring::Scoring,
+    searcher::{Found, Searched, SearcherKey},
+    table::{Column, Table},
 };
 use crate::{
+    formula::Formula,
+    lipid::{Derivative, FattyAcid},
     parser::Parsed,
-    utils::{with_index, Display, DroppedFileExt, RangeBoundsExt, UiExt},
+    utils::{with_index, Display, DroppedFileExt, RangeBoundsExt, SelectableValueFromIter, UiExt},
+    widget::{
+        depiction, editor,
+        molecule::Molecule,
+        molfile::{Record, Version},
+        series, Editor,
+    },
 };
 use anyhow::Error;
 use bitflags::bitflags;
@@ -13,25 +35,36 @@ use egui::{
     global_dark_light_mode_switch,
     menu::bar,
     plot::{
-        self, Bar, BarChart, CoordinatesFormatter, Corner, HLine, Legend, MarkerShape, Plot,
-        PlotPoint, Points, Text, VLine,
+        self, Bar, BarChart, CoordinatesFormatter, Corner, HLine, Legend, LineStyle, MarkerShape,
+        Plot, PlotPoint, Points, Text, VLine,
     },
-    warn_if_debug_build, Align, Align2, CentralPanel, Color32, Context, DragValue, DroppedFile, Id,
-    LayerId, Layout, Order, Response, RichText, SidePanel, Slider, TextStyle, TopBottomPanel, Ui,
-    WidgetText, Window,
+    vec2, warn_if_debug_build, Align, Align2, Button, CentralPanel, Color32, ComboBox, Context,
+    DragValue, DroppedFile, Grid, Id, InnerResponse, LayerId, Layout, Order, Rect, RichText,
+    ScrollArea, Sense, SidePanel, TextEdit, TextStyle, TopBottomPanel, Ui, Vec2, WidgetText,
+    Window,
 };
 use indexmap::IndexMap;
-use ndarray::{Array1, Dimension};
+use itertools::Itertools;
+use ndarray::Array1;
 use ndarray_stats::{interpolate::Linear, Quantile1dExt};
 use noisy_float::types::n64;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt::{self, Write},
     ops::{Bound, RangeBounds},
 };
 use tracing::{error, info};
 
+/// Molfile or SD file
+fn is_structure(file: &DroppedFile) -> bool {
+    let name = match &file.path {
+        Some(path) => path.to_string_lossy().to_lowercase(),
+        None => file.name.to_lowercase(),
+    };
+    name.ends_with(".mol") || name.ends_with(".sdf")
+}
+
 pub fn color(index: usize) -> Color32 {
     let golden_ratio: f32 = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
     let h = index as f32 * golden_ratio;
@@ -48,6 +81,7 @@ pub struct App {
     filter: HashSet<usize>,
 
     left_panel: bool,
+    right_panel: bool,
     label: Label,
 
     // Filter
@@ -57,11 +91,55 @@ pub struct App {
     // Find
     mass: usize,
     pattern: Vec<Vec<usize>>,
+    features: Vec<Feature>,
     count: usize,
+    scoring: Scoring,
+    penalty: u8,
+    direction: Direction,
+    anchor: Anchor,
+    anchor_threshold: u64,
+    table: Table,
+    explanation: Explanation,
+    noise: u8,
+    #[serde(skip)]
+    molecular_ion: Option<MolecularIon>,
+
+    // Double bonds
+    chain: Chain,
+    #[serde(skip)]
+    positions: Vec<usize>,
+
+    // Lipid
+    lipid: String,
+    derivative: Derivative,
+    reference: bool,
+
+    // Isotopes
+    formula: String,
+    isotopes: bool,
+    fine_structure: bool,
+    clusters: bool,
+
+    // Composition
+    composition: Composition,
+    #[serde(skip)]
+    peak: Option<usize>,
+
+    // Neutral losses
+    losses: Losses,
+
+    // Differences
+    differences: Differences,
 
     // Statistics
     statistics: Statistics,
 
+    // Structures
+    structures: Structures,
+    drawing: Drawing,
+    fragmentation: Fragmentation,
+    ranking: Ranking,
+
     // Peak Finder
     lag: usize,
     threshold: f64,
@@ -114,6 +192,17 @@ impl App {
             (!input.raw.dropped_files.is_empty()).then_some(input.raw.dropped_files.clone())
         }) {
             info!(?files);
+            // Structures (linked to the spectra by CAS or name)
+            let (structures, files): (Vec<_>, Vec<_>) = files.into_iter().partition(is_structure);
+            for file in structures {
+                match file.content().and_then(|content| Record::read(&content)) {
+                    Ok(records) => self.structures.records.extend(records),
+                    Err(error) => error!(%error),
+                }
+            }
+            if files.is_empty() {
+                return;
+            }
             self.files = files;
             for (index, file) in self.files.iter().enumerate() {
                 let content = match file.content() {
@@ -135,6 +224,42 @@ impl App {
                 self.parsed.insert(index, parsed);
                 self.colors.insert(index, color(index));
             }
+            if self.mass == 0 && self.parsed.contains_key(&0) {
+                self.find_molecular_ion(ctx);
+            }
+            self.chain_from_name();
+        }
+    }
+
+    /// Chain (and expected double bond positions) from the file name or the
+    /// `Name` field
+    fn chain_from_name(&mut self) {
+        let names = [
+            self.files.first().map(|file| file.display().to_string()),
+            self.parsed.get(&0).map(|parsed| parsed.name.clone()),
+        ];
+        if let Some((chain, positions)) = names
+            .into_iter()
+            .flatten()
+            .find_map(|name| Chain::from_name(&name))
+        {
+            self.lipid = match &positions[..] {
+                [] => chain.to_string(),
+                positions => format!("{}-{chain}", positions.iter().join(",")),
+            };
+            self.chain = chain;
+            self.positions = positions;
+        }
+    }
+
+    fn find_molecular_ion(&mut self, ctx: &Context) {
+        let Some(parsed) = self.parsed.get(&0) else {
+            return;
+        };
+        self.molecular_ion = MolecularIon::find(&self.peaks(ctx), self.noise, parsed.mw);
+        if let Some(molecular_ion) = &self.molecular_ion {
+            info!(?molecular_ion);
+            self.mass = molecular_ion.mass;
         }
     }
 
@@ -156,11 +281,62 @@ impl App {
                 ui.centered_and_justified(|ui| ui.label("Drag and drop .msp file"))
                     .response
             } else {
+                let parsed = &self.parsed[&0];
                 ui.vertical_centered_justified(|ui| {
-                    ui.heading(&self.parsed[&0].name);
+                    ui.heading(&parsed.name);
+                    if let Ok(formula) = parsed.formula.parse::<Formula>() {
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "{formula}: {} (monoisotopic {:.4}, average {:.2})",
+                                formula.nominal(),
+                                formula.monoisotopic(),
+                                formula.average(),
+                            ));
+                            if let Some(mismatch) = parsed.mw.and_then(|mw| formula.mismatch(mw)) {
+                                ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                                    .on_hover_text(mismatch.to_string());
+                            }
+                        });
+                    }
+                    if let Some(record) = self
+                        .structures
+                        .records
+                        .iter()
+                        .find(|record| record.matches(parsed))
+                    {
+                        ui.label(format!("Structure: {}", record.molecule.smiles()));
+                    }
                 });
                 ui.separator();
-                self.plot(ui)
+                let response = self.plot(ui);
+                let (clicked, pointed) = response.inner;
+                if let Some(mass) = clicked {
+                    self.mass = mass;
+                }
+                if response.response.secondary_clicked() {
+                    self.peak = pointed;
+                }
+                response.response.context_menu(|ui| {
+                    let Some(mass) = self.peak else {
+                        ui.label("No peak");
+                        return;
+                    };
+                    ui.label(format!("Peak {mass}"));
+                    if ui.button("🔍 Find").clicked() {
+                        self.mass = mass;
+                        ui.close_menu();
+                    }
+                    if ui
+                        .button("🧪 Compose")
+                        .on_hover_text("Elemental compositions of the peak mass")
+                        .clicked()
+                    {
+                        self.composition.mass = mass as _;
+                        self.composition.ppm = None;
+                        self.composition.show = true;
+                        ui.close_menu();
+                    }
+                })
             }
         });
     }
@@ -226,10 +402,68 @@ impl App {
                 ui.heading("Input");
                 ui.separator();
                 ui.horizontal(|ui| {
-                    ui.label("Mass:");
-                    ui.add(DragValue::new(&mut self.mass).clamp_range(0..=self.bounds.mass.end()));
-                    if ui.button("🔍").clicked() {}
+                    ui.label("Direction:");
+                    ComboBox::from_id_source("direction")
+                        .selected_text(self.direction.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value_from_iter(
+                                &mut self.direction,
+                                Direction::ALL.into_iter(),
+                            );
+                        });
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Anchor:");
+                    ComboBox::from_id_source("anchor")
+                        .selected_text(self.anchor.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value_from_iter(
+                                &mut self.anchor,
+                                Anchor::ALL.into_iter(),
+                            );
+                        });
                 });
+                match self.anchor {
+                    Anchor::Mass => {
+                        ui.horizontal(|ui| {
+                            ui.label("Mass:");
+                            ui.add(
+                                DragValue::new(&mut self.mass)
+                                    .clamp_range(0..=self.bounds.mass.end()),
+                            );
+                            if ui
+                                .button("🔍")
+                                .on_hover_text("Find the molecular ion")
+                                .clicked()
+                            {
+                                self.find_molecular_ion(ui.ctx());
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.label("Noise:");
+                            ui.drag_percent(&mut self.noise);
+                        })
+                        .response
+                        .on_hover_text("the noise threshold of the molecular ion search");
+                        if let Some(molecular_ion) = &self.molecular_ion {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("M⁺: {}", molecular_ion.mass));
+                                if !molecular_ion.warnings.is_empty() {
+                                    ui.colored_label(ui.visuals().warn_fg_color, "⚠")
+                                        .on_hover_text(molecular_ion.warnings.iter().join("\n"));
+                                }
+                            });
+                        }
+                    }
+                    Anchor::Any => {
+                        ui.horizontal(|ui| {
+                            ui.label("Threshold:");
+                            ui.add(DragValue::new(&mut self.anchor_threshold));
+                        })
+                        .response
+                        .on_hover_text("the minimum intensity of an anchor peak");
+                    }
+                }
                 let mut repeat = None;
                 self.pattern.retain_mut(|step| {
                     ui.horizontal(|ui| {
@@ -256,14 +490,237 @@ impl App {
                 if let Some(step) = repeat {
                     self.pattern.push(step);
                 }
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Features:");
+                    for feature in Feature::ALL {
+                        let mut checked = self.features.contains(&feature);
+                        if ui
+                            .checkbox(&mut checked, feature.to_string())
+                            .on_hover_text(format!("gap signature {:?}", feature.signature()))
+                            .changed()
+                        {
+                            if checked {
+                                self.features.push(feature);
+                            } else {
+                                self.features.retain(|&other| other != feature);
+                            }
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("the feature templates searched alongside the pattern");
+                // Scoring
+                ui.separator();
+                ui.heading("Scoring");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Strategy:");
+                    ComboBox::from_id_source("scoring")
+                        .selected_text(self.scoring.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value_from_iter(
+                                &mut self.scoring,
+                                Scoring::ALL.into_iter(),
+                            );
+                        });
+                });
+                if let Scoring::Penalized = self.scoring {
+                    ui.horizontal(|ui| {
+                        ui.label("Penalty:");
+                        ui.drag_percent(&mut self.penalty);
+                    })
+                    .response
+                    .on_hover_text("the cost of a missing step relative to the base peak");
+                }
                 // Output
                 ui.separator();
                 ui.heading("Output");
                 ui.separator();
                 ui.horizontal(|ui| {
                     ui.label("Count:");
-                    ui.add(Slider::new(&mut self.count, 0..=10));
+                    ui.add(DragValue::new(&mut self.count));
+                });
+            });
+            ui.collapsing(WidgetText::from("Double bonds").heading(), |ui| {
+                // Chain
+                ui.separator();
+                ui.heading("Chain");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Carbons:");
+                    ui.add(DragValue::new(&mut self.chain.carbons).clamp_range(3..=40));
+                    ui.label("Bonds:");
+                    ui.add(DragValue::new(&mut self.chain.bonds).clamp_range(0..=6));
+                    if ui
+                        .button("📄")
+                        .on_hover_text("From the file name")
+                        .clicked()
+                    {
+                        self.chain_from_name();
+                    }
+                });
+                // Positions
+                ui.separator();
+                ui.heading("Positions");
+                ui.separator();
+                if !self.parsed.contains_key(&0) {
+                    return;
+                }
+                let isomers = match self.isomers(ui.ctx()) {
+                    Ok(isomers) => isomers,
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, error);
+                        return;
+                    }
+                };
+                let height = ui.text_style_height(&TextStyle::Body);
+                ScrollArea::vertical().max_height(10.0 * height).show_rows(
+                    ui,
+                    height,
+                    isomers.len(),
+                    |ui, rows| {
+                        for rank in rows {
+                            let isomer = &isomers[rank];
+                            let mut text =
+                                format!("{rank}. {isomer} ({:.2})", isomer.prediction.score.value);
+                            if isomer.positions == self.positions {
+                                text.push_str(" ✔");
+                            }
+                            ui.label(text).on_hover_text(
+                                isomer.diagnostics.iter().join("\n")
+                                    + &format!("\n{}", isomer.prediction.score),
+                            );
+                        }
+                    },
+                );
+            });
+            ui.collapsing(WidgetText::from("Lipid").heading(), |ui| {
+                ui.separator();
+                ui.heading("Fatty acid");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Shorthand:");
+                    ui.text_edit_singleline(&mut self.lipid)
+                        .on_hover_text("18:2(9Z,12Z), 9,12-18:2, 18:1n-9, i-15:0, cy-19:0(11)");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Derivative:");
+                    ComboBox::from_id_source("derivative")
+                        .selected_text(self.derivative.to_string())
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value_from_iter(
+                                &mut self.derivative,
+                                Derivative::ALL.into_iter(),
+                            );
+                        });
                 });
+                ui.separator();
+                ui.heading("Expected ions");
+                ui.separator();
+                match self.lipid.parse::<FattyAcid>() {
+                    Ok(fatty_acid) => {
+                        let ions = fatty_acid.ions(self.derivative);
+                        ui.horizontal(|ui| {
+                            ui.label(format!("M⁺: {}", ions[0].mass));
+                            if ui
+                                .button("🔍")
+                                .on_hover_text("Set the Finder mass")
+                                .clicked()
+                            {
+                                self.mass = ions[0].mass;
+                            }
+                        });
+                        ui.checkbox(&mut self.reference, "Overlay")
+                            .on_hover_text("Show the expected ions on the plot");
+                        let structure = series::fatty_acid(&fatty_acid, Some(self.derivative));
+                        let response = ui.add_enabled(structure.is_ok(), Button::new("🏆 Ranking"));
+                        match &structure {
+                            Ok(molecule) => {
+                                if response
+                                    .on_hover_text("add the structure to the ranking candidates")
+                                    .clicked()
+                                {
+                                    self.ranking.candidates.push('\n');
+                                    self.ranking.candidates.push_str(&molecule.smiles());
+                                    self.ranking.show = true;
+                                }
+                            }
+                            Err(error) => {
+                                response.on_disabled_hover_text(error.to_string());
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                    }
+                }
+            });
+            ui.collapsing(WidgetText::from("Isotopes").heading(), |ui| {
+                ui.separator();
+                ui.heading("Theoretical pattern");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Formula:");
+                    ui.text_edit_singleline(&mut self.formula)
+                        .on_hover_text("the proposed formula (empty for the parsed one)");
+                });
+                ui.checkbox(&mut self.isotopes, "Overlay")
+                    .on_hover_text("Show the theoretical pattern at the molecular ion");
+                ui.checkbox(&mut self.fine_structure, "Fine structure")
+                    .on_hover_text("Don't collapse the pattern to nominal masses");
+                ui.separator();
+                ui.heading("Ratios");
+                ui.separator();
+                match self.proposed() {
+                    Ok(_) if !self.parsed.contains_key(&0) => {}
+                    Ok(formula) => {
+                        let pattern = self.isotopes(ctx, &formula).pattern;
+                        let mass = self.monoisotopic(&formula);
+                        let peaks = self.peaks(ctx);
+                        let observed = peaks.get(&mass).copied().unwrap_or_default();
+                        Grid::new("isotopes").striped(true).show(ui, |ui| {
+                            ui.label("Mass");
+                            ui.label("Theoretical");
+                            ui.label("Observed");
+                            ui.end_row();
+                            for (nominal, abundance) in pattern.nominal() {
+                                let offset = nominal as isize - formula.nominal() as isize;
+                                let label = match offset {
+                                    0 => "M".to_owned(),
+                                    offset => format!("M{offset:+}"),
+                                };
+                                let intensity = mass
+                                    .checked_add_signed(offset)
+                                    .and_then(|mass| peaks.get(&mass))
+                                    .copied()
+                                    .unwrap_or_default();
+                                ui.label(label);
+                                ui.label(format!("{:.2}%", abundance * 100.0));
+                                if observed == 0 {
+                                    ui.label("-");
+                                } else {
+                                    ui.label(format!(
+                                        "{:.2}%",
+                                        intensity as f64 / observed as f64 * 100.0,
+                                    ));
+                                }
+                                ui.end_row();
+                            }
+                        });
+                        ui.label(format!("Fit: {:.4}", pattern.fit(&peaks, mass).score))
+                            .on_hover_text("the cosine similarity of the patterns");
+                    }
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                    }
+                }
+                ui.separator();
+                ui.heading("Clusters");
+                ui.separator();
+                ui.checkbox(&mut self.clusters, "Annotate").on_hover_text(
+                    "Show the carbon count (of M+1/M) and the heteroatoms (of M+2/M) of the \
+                     isotope clusters above the noise",
+                );
             });
             ui.collapsing(WidgetText::from("Statistics").heading(), |ui| {
                 ui.separator();
@@ -338,7 +795,54 @@ impl App {
                     // the influence (between 0 and 1) of new signals on the mean and standard deviation
                 });
             });
-            // ui.add(toggle(&mut self.temp));
+        });
+    }
+
+    fn right_panel(&mut self, ctx: &Context) {
+        // Depict the structures of the spectrum: the linked one, the
+        // fragmented one and the selected candidate
+        if !self.right_panel {
+            return;
+        }
+        let Some(parsed) = self.parsed.get(&0) else {
+            return;
+        };
+        let mut molecules = Vec::new();
+        if let Some(record) = self
+            .structures
+            .records
+            .iter()
+            .find(|record| record.matches(parsed))
+        {
+            molecules.push(("Linked".to_owned(), record.molecule.clone()));
+        }
+        if self.fragmentation.show {
+            if let Ok(molecule) = self.fragmentation.smiles.parse() {
+                molecules.push(("Fragmented".to_owned(), molecule));
+            }
+        }
+        if let (true, Some(key)) = (self.ranking.show, &self.ranking.selected) {
+            let peaks = self.peaks(ctx);
+            let structures = self.ranked(ctx, &peaks);
+            if let Some((index, molecule)) = structures
+                .iter()
+                .position(|structure| &structure.key == key)
+                .and_then(|index| Some((index, structures[index].smiles.parse().ok()?)))
+            {
+                molecules.push((format!("Candidate {}", index + 1), molecule));
+            }
+        }
+        SidePanel::right("right_panel").show_animated(ctx, !molecules.is_empty(), |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                for (title, molecule) in &molecules {
+                    ui.label(RichText::new(title).heading());
+                    let width = ui.available_width();
+                    ui.add(depiction(molecule, vec2(width, 0.75 * width)));
+                    ui.label(molecule.smiles())
+                        .on_hover_text(molecule.formula().to_string());
+                    ui.separator();
+                }
+            });
         });
     }
 
@@ -348,7 +852,17 @@ impl App {
                 global_dark_light_mode_switch(ui);
                 ui.separator();
                 ui.toggle_value(&mut self.left_panel, "🛠 Control");
+                ui.toggle_value(&mut self.right_panel, "🖼 Depiction");
                 ui.toggle_value(&mut self.errors.show, "⚠ Errors");
+                ui.toggle_value(&mut self.table.show, "📋 Predictions");
+                ui.toggle_value(&mut self.explanation.show, "🔎 Explanation");
+                ui.toggle_value(&mut self.composition.show, "🧪 Composition");
+                ui.toggle_value(&mut self.losses.show, "➖ Losses");
+                ui.toggle_value(&mut self.differences.show, "↔ Differences");
+                ui.toggle_value(&mut self.structures.show, "⌬ Structures");
+                ui.toggle_value(&mut self.drawing.show, "✏ Editor");
+                ui.toggle_value(&mut self.fragmentation.show, "💥 Fragmentation");
+                ui.toggle_value(&mut self.ranking.show, "🏆 Ranking");
             });
         });
     }
@@ -403,10 +917,842 @@ impl App {
             }
         }
     }
+
+    fn table(&mut self, ctx: &Context) {
+        // Show predictions table (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let predictions = self.predictions(ctx, &peaks);
+        let ranks = self.table.order(&predictions);
+        Window::new("Predictions")
+            .open(&mut self.table.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.menu_button("Export", |ui| {
+                        if ui.button("CSV").on_hover_text("Copy as CSV").clicked() {
+                            let csv = table::csv(&predictions, &ranks);
+                            ui.output_mut(|output| output.copied_text = csv);
+                            ui.close_menu();
+                        }
+                        if ui.button("JSON").on_hover_text("Copy as JSON").clicked() {
+                            match table::json(&predictions, &ranks) {
+                                Ok(json) => ui.output_mut(|output| output.copied_text = json),
+                                Err(error) => error!(%error),
+                            }
+                            ui.close_menu();
+                        }
+                    });
+                    if ui.button("Clear selection").clicked() {
+                        self.table.selected.clear();
+                    }
+                });
+                ui.separator();
+                ScrollArea::both().show(ui, |ui| {
+                    Grid::new("predictions").striped(true).show(ui, |ui| {
+                        for column in Column::ALL {
+                            let mut text = column.to_string();
+                            if self.table.sort.column == column {
+                                text.push(if self.table.sort.descending {
+                                    '⏷'
+                                } else {
+                                    '⏶'
+                                });
+                            }
+                            if ui.add(Button::new(text).frame(false)).clicked() {
+                                self.table.sort.toggle(column);
+                            }
+                        }
+                        ui.end_row();
+                        for rank in ranks {
+                            let prediction = &predictions[rank];
+                            let selected = self.table.selected.contains(prediction);
+                            let response = ui.selectable_label(selected, rank.to_string());
+                            if response.clicked() {
+                                self.table.selected.toggle(prediction);
+                            }
+                            response.context_menu(|ui| {
+                                if ui.button("Explain").clicked() {
+                                    self.explanation.show = true;
+                                    self.explanation.rank = rank;
+                                    ui.close_menu();
+                                }
+                                if ui.button("Compare").clicked() {
+                                    self.explanation.show = true;
+                                    self.explanation.compare = Some(rank);
+                                    ui.close_menu();
+                                }
+                            });
+                            ui.label(format!("{:.2}", prediction.score.value))
+                                .on_hover_text(prediction.score.to_string());
+                            ui.label(prediction.anchor.to_string());
+                            ui.label(prediction.steps.iter().map(|step| step.mass).join(" "));
+                            ui.label(prediction.steps.iter().map(|step| step.delta).join(" "));
+                            ui.label(
+                                prediction
+                                    .steps
+                                    .iter()
+                                    .map(|step| {
+                                        step.intensity.map_or("-".to_owned(), |intensity| {
+                                            intensity.to_string()
+                                        })
+                                    })
+                                    .join(" "),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn composition(&mut self, ctx: &Context) {
+        // Show the elemental compositions of a mass (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let candidates = self.candidates(ctx, &peaks);
+        let mut proposed = None;
+        let composition = &mut self.composition;
+        Window::new("Composition")
+            .open(&mut composition.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Mass:");
+                    ui.add(
+                        DragValue::new(&mut composition.mass)
+                            .clamp_range(0.0..=f64::MAX)
+                            .max_decimals(5),
+                    )
+                    .on_hover_text("the ion mass (of a radical cation)");
+                    ui.label("ppm:");
+                    ui.drag_option(&mut composition.ppm, 0.0..=1000.0, 0.1)
+                        .on_hover_text("the tolerance of an accurate mass");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("RDB:");
+                    ui.add(
+                        DragValue::new(&mut composition.rdb.0)
+                            .clamp_range(-0.5..=composition.rdb.1)
+                            .speed(0.5),
+                    );
+                    ui.add(
+                        DragValue::new(&mut composition.rdb.1)
+                            .clamp_range(composition.rdb.0..=f64::MAX)
+                            .speed(0.5),
+                    );
+                })
+                .response
+                .on_hover_text("the range of rings plus double bonds");
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut composition.nitrogen_rule, "Nitrogen rule")
+                        .on_hover_text("Odd-electron ion: odd nitrogens for an odd mass");
+                    ui.checkbox(&mut composition.senior, "Senior rules")
+                        .on_hover_text("Sum of the valences even and large enough");
+                    ui.label("Count:");
+                    ui.add(DragValue::new(&mut composition.count).clamp_range(1..=1000));
+                });
+                ui.collapsing("Elements", |ui| {
+                    Grid::new("elements").show(ui, |ui| {
+                        ui.label("Symbol");
+                        ui.label("Min");
+                        ui.label("Max");
+                        ui.end_row();
+                        composition.ranges.retain_mut(|range| {
+                            let symbol = TextEdit::singleline(&mut range.symbol);
+                            ui.add(symbol.desired_width(32.0));
+                            ui.add(DragValue::new(&mut range.min).clamp_range(0..=range.max));
+                            let max = DragValue::new(&mut range.max);
+                            ui.add(max.clamp_range(range.min..=usize::MAX));
+                            let keep = !ui.button("🗙").clicked();
+                            ui.end_row();
+                            keep
+                        });
+                    });
+                    if ui.button("➕").on_hover_text("Add an element").clicked() {
+                        composition.ranges.push(Default::default());
+                    }
+                });
+                ui.separator();
+                if candidates.is_empty() {
+                    ui.label("No candidates");
+                    return;
+                }
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("candidates").striped(true).show(ui, |ui| {
+                        for column in ["Rank", "Formula", "RDB", "Error", "Fit"] {
+                            ui.heading(column);
+                        }
+                        ui.end_row();
+                        for (rank, candidate) in candidates.iter().enumerate() {
+                            ui.label(rank.to_string());
+                            ui.label(candidate.formula.to_string()).context_menu(|ui| {
+                                if ui
+                                    .button("Isotopes")
+                                    .on_hover_text("Propose for the isotope pattern")
+                                    .clicked()
+                                {
+                                    proposed = Some(candidate.formula.to_string());
+                                    ui.close_menu();
+                                }
+                            });
+                            ui.label(format!("{:.1}", candidate.rdb));
+                            ui.label(format!("{:.2} ppm", candidate.error));
+                            ui.label(format!("{:.4}", candidate.fit.score))
+                                .on_hover_text("the cosine similarity of the isotope patterns");
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+        if let Some(formula) = proposed {
+            self.formula = formula;
+        }
+    }
+
+    fn drawing(&mut self, ctx: &Context) {
+        // Draw a structure for the fragmentation and the ranking
+        let drawing = &mut self.drawing.editor;
+        let fragmentation = &mut self.fragmentation;
+        let ranking = &mut self.ranking;
+        Window::new("Editor")
+            .open(&mut self.drawing.show)
+            .show(ctx, |ui| {
+                if let Ok(molecule) = fragmentation.smiles.parse::<Molecule>() {
+                    if ui
+                        .button("⤵ Fragmentation")
+                        .on_hover_text("draw the structure of the fragmentation")
+                        .clicked()
+                    {
+                        drawing.load(&molecule);
+                    }
+                }
+                ui.add(editor(drawing));
+                if drawing.molecule.node_count() == 0 {
+                    return;
+                }
+                let smiles = drawing.completed().smiles();
+                ui.horizontal(|ui| {
+                    ui.label(format!("SMILES: {smiles}"));
+                    if ui.button("📋").on_hover_text("copy").clicked() {
+                        ui.output_mut(|output| output.copied_text = smiles.clone());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("💥 Fragmentation")
+                        .on_hover_text("fragment the structure")
+                        .clicked()
+                    {
+                        fragmentation.smiles = smiles.clone();
+                        fragmentation.show = true;
+                    }
+                    if ui
+                        .button("🏆 Ranking")
+                        .on_hover_text("add the structure to the candidates")
+                        .clicked()
+                    {
+                        ranking.candidates.push('\n');
+                        ranking.candidates.push_str(&smiles);
+                        ranking.show = true;
+                    }
+                });
+            });
+    }
+
+    fn fragmentation(&mut self, ctx: &Context) {
+        // Show the predicted spectrum of a structure (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let fragments =
+            ctx.memory_mut(|memory| memory.caches.cache::<Fragmented>().get(&self.fragmentation));
+        let parsed = &self.parsed[&0];
+        let predicted = spectrum(&parsed.name, &fragments);
+        let linked = self
+            .structures
+            .records
+            .iter()
+            .find(|record| record.matches(parsed));
+        let color = self.colors[0];
+        let fragmentation = &mut self.fragmentation;
+        Window::new("Fragmentation")
+            .open(&mut fragmentation.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Structure:");
+                    ui.text_edit_singleline(&mut fragmentation.smiles)
+                        .on_hover_text("SMILES of the structure");
+                    if let Some(record) = linked {
+                        if ui
+                            .button("🔗")
+                            .on_hover_text("Structure linked to the spectrum")
+                            .clicked()
+                        {
+                            fragmentation.smiles = record.molecule.smiles();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Depth:");
+                    ui.add(DragValue::new(&mut fragmentation.depth).clamp_range(1..=4))
+                        .on_hover_text("the maximum number of consecutive cleavages");
+                    ui.checkbox(&mut fragmentation.alpha_cleavage, "α-cleavage");
+                    ui.checkbox(&mut fragmentation.mclafferty, "McLafferty");
+                    ui.checkbox(
+                        &mut fragmentation.hydrogen_rearrangements,
+                        "H rearrangements",
+                    );
+                });
+                if let Err(error) = fragmentation.smiles.parse::<Molecule>() {
+                    ui.colored_label(ui.visuals().warn_fg_color, error.to_string());
+                    return;
+                }
+                ui.label(format!(
+                    "Similarity: {:.3}",
+                    similarity(&peaks, &predicted.peaks)
+                ))
+                .on_hover_text("the weighted cosine similarity of the spectra");
+                ui.separator();
+                // Mirror plot: observed up, predicted down
+                let relative = |peaks: &BTreeMap<usize, u64>, sign: f64| {
+                    let base = peaks.values().copied().max().unwrap_or_default().max(1) as f64;
+                    peaks
+                        .iter()
+                        .map(|(&mass, &intensity)| {
+                            Bar::new(mass as _, sign * intensity as f64 / base * 100.0).name(mass)
+                        })
+                        .collect()
+                };
+                Plot::new("mirror")
+                    .height(ui.available_width() / 2.0)
+                    .legend(Legend::default())
+                    .show(ui, |plot_ui| {
+                        plot_ui.bar_chart(
+                            BarChart::new(relative(&peaks, 1.0))
+                                .name("Observed")
+                                .color(color),
+                        );
+                        plot_ui.bar_chart(
+                            BarChart::new(relative(&predicted.peaks, -1.0))
+                                .name("Predicted")
+                                .color(Color32::GRAY),
+                        );
+                    });
+                ui.separator();
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("fragments").striped(true).show(ui, |ui| {
+                        ui.label("Mass");
+                        ui.label("Formula");
+                        ui.label("Rule");
+                        ui.label("Depth");
+                        ui.label("Weight");
+                        ui.end_row();
+                        for fragment in &fragments {
+                            ui.label(fragment.mass.to_string());
+                            ui.label(fragment.formula.to_string());
+                            ui.label(fragment.rule.to_string());
+                            ui.label(fragment.depth.to_string());
+                            ui.label(format!("{:.3}", fragment.weight));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn ranking(&mut self, ctx: &Context) {
+        // Rank the candidate structures against the spectrum (if any file is
+        // parsed)
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let structures = self.ranked(ctx, &peaks);
+        let records = &self.structures.records;
+        let smiles = &self.fragmentation.smiles;
+        let ranking = &mut self.ranking;
+        Window::new("Ranking")
+            .open(&mut ranking.show)
+            .show(ctx, |ui| {
+                ui.label("Candidates:");
+                ui.add(
+                    TextEdit::multiline(&mut ranking.candidates)
+                        .desired_rows(4)
+                        .hint_text("SMILES, one per line"),
+                );
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(!records.is_empty(), Button::new("⌬ Structures"))
+                        .on_hover_text("add the structures of the library")
+                        .clicked()
+                    {
+                        for record in records {
+                            ranking.candidates.push('\n');
+                            ranking.candidates.push_str(&record.molecule.smiles());
+                        }
+                    }
+                    if ui
+                        .add_enabled(!smiles.is_empty(), Button::new("💥 Fragmentation"))
+                        .on_hover_text("add the structure of the fragmentation")
+                        .clicked()
+                    {
+                        ranking.candidates.push('\n');
+                        ranking.candidates.push_str(smiles);
+                    }
+                    if ui.button("🗑").on_hover_text("clear").clicked() {
+                        ranking.candidates.clear();
+                        ranking.selected = None;
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Isomers:");
+                    ui.text_edit_singleline(&mut ranking.isomers)
+                        .on_hover_text("acyclic hydrocarbon formula (C6H12)");
+                    let hydrocarbon = ranking
+                        .isomers
+                        .parse()
+                        .and_then(|formula| series::hydrocarbon(&formula));
+                    let response = ui.add_enabled(hydrocarbon.is_ok(), Button::new("➕"));
+                    match hydrocarbon {
+                        Ok((carbons, bonds)) => {
+                            if response
+                                .on_hover_text("add the straight-chain isomers of the formula")
+                                .clicked()
+                            {
+                                for molecule in
+                                    series::isomers(carbons, bonds).into_iter().flatten()
+                                {
+                                    ranking.candidates.push('\n');
+                                    ranking.candidates.push_str(&molecule.smiles());
+                                }
+                            }
+                        }
+                        Err(error) => {
+                            response.on_disabled_hover_text(error.to_string());
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Threshold:");
+                    ui.add(
+                        DragValue::new(&mut ranking.threshold)
+                            .clamp_range(0..=1000)
+                            .suffix("‰"),
+                    )
+                    .on_hover_text("the relative intensity of the peaks to explain");
+                });
+                ui.separator();
+                if structures.is_empty() {
+                    ui.label("No candidates");
+                    return;
+                }
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("ranking").striped(true).show(ui, |ui| {
+                        ui.label("Rank");
+                        ui.label("SMILES");
+                        ui.label("Formula");
+                        ui.label("Score");
+                        ui.label("Explained");
+                        ui.label("Coverage");
+                        ui.label("Similarity");
+                        ui.end_row();
+                        for (index, structure) in structures.iter().enumerate() {
+                            let selected = ranking.selected.as_ref() == Some(&structure.key);
+                            if ui
+                                .selectable_label(selected, (index + 1).to_string())
+                                .on_hover_text("annotate the explained peaks on the plot")
+                                .clicked()
+                            {
+                                ranking.selected = (!selected).then(|| structure.key.clone());
+                            }
+                            ui.label(&structure.smiles).on_hover_ui(|ui| {
+                                if let Ok(molecule) = structure.smiles.parse::<Molecule>() {
+                                    ui.add(depiction(&molecule, vec2(200.0, 150.0)));
+                                }
+                            });
+                            ui.label(&structure.formula);
+                            ui.label(format!("{:.3}", structure.score));
+                            ui.label(format!("{}/{}", structure.explained.len(), structure.peaks));
+                            ui.label(format!("{:.1}%", structure.coverage * 100.0));
+                            ui.label(format!("{:.3}", structure.similarity));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn structures(&mut self, ctx: &Context) {
+        // Show the structure library filtered by a substructure
+        let found = self.found(ctx);
+        let structures = &mut self.structures;
+        Window::new("Structures")
+            .open(&mut structures.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Substructure:");
+                    ui.text_edit_singleline(&mut structures.query)
+                        .on_hover_text("SMILES of the substructure (empty for all)");
+                });
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut structures.any_element, "Any element")
+                        .on_hover_text("the aromatic atoms match any aromatic atom");
+                    ui.checkbox(&mut structures.any_bond, "Any bond")
+                        .on_hover_text("the bonds match any bond order");
+                });
+                let Found { query, records } = match found {
+                    Ok(found) => found,
+                    Err(error) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, error);
+                        return;
+                    }
+                };
+                ui.separator();
+                if structures.records.is_empty() {
+                    ui.label("Drag and drop .mol or .sdf file");
+                    return;
+                }
+                ui.menu_button("Export", |ui| {
+                    for version in [Version::V2000, Version::V3000] {
+                        if ui
+                            .button(format!("SDF {version:?}"))
+                            .on_hover_text("Copy the found structures as an SD file")
+                            .clicked()
+                        {
+                            let sdf = records
+                                .iter()
+                                .map(|&index| structures.records[index].write(version))
+                                .collect::<String>();
+                            ui.output_mut(|output| output.copied_text = sdf);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                // The matches of the shown rows only
+                let height = ui.text_style_height(&TextStyle::Body);
+                ScrollArea::both().show_rows(ui, height, records.len() + 1, |ui, rows| {
+                    Grid::new("structures").striped(true).show(ui, |ui| {
+                        for row in rows {
+                            let Some(&index) = row.checked_sub(1).and_then(|row| records.get(row))
+                            else {
+                                ui.label("Name");
+                                ui.label("CAS");
+                                ui.label("Formula");
+                                ui.label("SMILES");
+                                ui.label("Matches");
+                                ui.end_row();
+                                continue;
+                            };
+                            let record = &structures.records[index];
+                            ui.label(&record.parsed.name);
+                            ui.label(
+                                record
+                                    .parsed
+                                    .cas
+                                    .map_or_else(String::new, |cas| cas.to_string()),
+                            );
+                            ui.label(&record.parsed.formula);
+                            ui.label(record.molecule.smiles()).on_hover_ui(|ui| {
+                                ui.add(depiction(&record.molecule, vec2(200.0, 150.0)));
+                            });
+                            match &query {
+                                Some(query) => {
+                                    ui.label(record.molecule.substructures(query).len().to_string())
+                                }
+                                None => ui.label("-"),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn losses(&mut self, ctx: &Context) {
+        // Show the neutral losses from the precursor (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let precursor = self.losses.precursor.unwrap_or(self.mass);
+        let fragments = self.losses.fragments(precursor, &peaks);
+        let losses = &mut self.losses;
+        Window::new("Neutral losses")
+            .open(&mut losses.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Precursor:");
+                    ui.drag_option(&mut losses.precursor, 0..=usize::MAX, 1.0)
+                        .on_hover_text("the precursor mass (the Finder mass if none)");
+                    ui.label(precursor.to_string());
+                    ui.checkbox(&mut losses.highlight, "Highlight")
+                        .on_hover_text("Show the pairs of the known losses on the plot");
+                });
+                ui.collapsing("Dictionary", |ui| {
+                    Grid::new("dictionary").show(ui, |ui| {
+                        ui.label("Loss");
+                        ui.label("Name");
+                        ui.end_row();
+                        losses.dictionary.retain_mut(|loss| {
+                            ui.add(DragValue::new(&mut loss.mass));
+                            ui.text_edit_singleline(&mut loss.name);
+                            let keep = !ui.button("🗙").clicked();
+                            ui.end_row();
+                            keep
+                        });
+                    });
+                    if ui.button("➕").on_hover_text("Add a loss").clicked() {
+                        losses.dictionary.push(Default::default());
+                    }
+                });
+                ui.separator();
+                if fragments.is_empty() {
+                    ui.label("No fragments");
+                    return;
+                }
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("losses").striped(true).show(ui, |ui| {
+                        for column in ["Fragment", "Intensity", "Loss", "Known"] {
+                            ui.heading(column);
+                        }
+                        ui.end_row();
+                        for fragment in &fragments {
+                            ui.label(fragment.mass.to_string());
+                            ui.label(fragment.intensity.to_string());
+                            if fragment.known.is_empty() {
+                                ui.label(fragment.loss.to_string());
+                            } else {
+                                ui.colored_label(
+                                    ui.visuals().selection.bg_fill,
+                                    fragment.loss.to_string(),
+                                );
+                            }
+                            ui.label(fragment.known.join("; "));
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+    }
+
+    fn differences(&mut self, ctx: &Context) {
+        // Show the pairwise mass differences (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let histogram = ctx.memory_mut(|memory| {
+            memory
+                .caches
+                .cache::<Differenced>()
+                .get((&peaks, self.differences.max))
+        });
+        let (masses, weights) = matrix(&peaks, self.differences.top);
+        let mut seed = None;
+        let differences = &mut self.differences;
+        Window::new("Differences")
+            .open(&mut differences.show)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Max delta:");
+                    ui.add(DragValue::new(&mut differences.max).clamp_range(1..=1000));
+                    ui.label("Peaks:");
+                    ui.add(DragValue::new(&mut differences.top).clamp_range(2..=100))
+                        .on_hover_text("the number of the most intense peaks in the matrix");
+                });
+                ui.separator();
+                ui.heading("Histogram");
+                let bars = histogram
+                    .iter()
+                    .map(|(&delta, &weight)| Bar::new(delta as _, weight).name(delta))
+                    .collect();
+                let response = Plot::new("histogram")
+                    .height(ui.available_width() / 3.0)
+                    .show(ui, |plot_ui| {
+                        plot_ui.bar_chart(
+                            BarChart::new(bars)
+                                .name("Deltas")
+                                .color(self.colors[0])
+                                .element_formatter(Box::new(
+                                    |Bar {
+                                         argument, value, ..
+                                     },
+                                     _| {
+                                        format!("Δ {argument}: {value:.3}")
+                                    },
+                                )),
+                        );
+                        plot_ui
+                            .plot_clicked()
+                            .then(|| plot_ui.pointer_coordinate())
+                            .flatten()
+                            .map(|point| point.x.round())
+                            .filter(|&delta| delta >= 1.0)
+                    });
+                if let Some(delta) = response.inner {
+                    seed = Some(delta as usize);
+                }
+                response
+                    .response
+                    .on_hover_text("Click a delta to seed a Finder step");
+                ui.separator();
+                ui.heading("Matrix");
+                let cell = 12.0;
+                let size = Vec2::splat(cell * masses.len() as f32);
+                let (response, painter) = ui.allocate_painter(size, Sense::click());
+                let color = self.colors[0];
+                for (row, weights) in weights.iter().enumerate() {
+                    for (column, &weight) in weights.iter().enumerate() {
+                        if row == column {
+                            continue;
+                        }
+                        let min = response.rect.min + vec2(column as f32, row as f32) * cell;
+                        let fill = color.linear_multiply(weight.sqrt() as _);
+                        painter.rect_filled(Rect::from_min_size(min, Vec2::splat(cell)), 0.0, fill);
+                    }
+                }
+                let pointed = response.hover_pos().and_then(|position| {
+                    let offset = (position - response.rect.min) / cell;
+                    let (row, column) = (offset.y as usize, offset.x as usize);
+                    (row != column && row < masses.len() && column < masses.len())
+                        .then_some((row, column))
+                });
+                if let Some((row, column)) = pointed {
+                    let delta = masses[row].abs_diff(masses[column]);
+                    if response.clicked() {
+                        seed = Some(delta);
+                    }
+                    response.on_hover_text(format!(
+                        "{} - {} = {delta} ({:.3})",
+                        masses[row].max(masses[column]),
+                        masses[row].min(masses[column]),
+                        weights[row][column],
+                    ));
+                }
+            });
+        if let Some(delta) = seed {
+            self.pattern.push(vec![delta]);
+        }
+    }
+
+    fn explanation(&mut self, ctx: &Context) {
+        // Show the explanation of a prediction (if any file is parsed):
+        if !self.parsed.contains_key(&0) {
+            return;
+        }
+        let peaks = self.peaks(ctx);
+        let predictions = self.predictions(ctx, &peaks);
+        let explanation = &mut self.explanation;
+        Window::new("Explanation")
+            .open(&mut explanation.show)
+            .show(ctx, |ui| {
+                let Some(last) = predictions.len().checked_sub(1) else {
+                    ui.label("No predictions");
+                    return;
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Prediction:");
+                    ui.add(DragValue::new(&mut explanation.rank).clamp_range(0..=last));
+                    let mut compare = explanation.compare.is_some();
+                    ui.checkbox(&mut compare, "Compare:");
+                    match (compare, &mut explanation.compare) {
+                        (true, Some(rank)) => {
+                            ui.add(DragValue::new(rank).clamp_range(0..=last));
+                        }
+                        (true, compared @ None) => *compared = Some(0),
+                        (false, compared) => *compared = None,
+                    }
+                });
+                let prediction = &predictions[explanation.rank.min(last)];
+                ui.label(prediction.score.to_string());
+                ui.separator();
+                Grid::new("explanation").striped(true).show(ui, |ui| {
+                    for column in [
+                        "Step",
+                        "Mass",
+                        "Delta",
+                        "Intensity",
+                        "Relative",
+                        "Neighbours",
+                        "Contribution",
+                    ] {
+                        ui.strong(column);
+                    }
+                    ui.end_row();
+                    for (index, evidence) in Evidence::collect(prediction, &peaks)
+                        .into_iter()
+                        .enumerate()
+                    {
+                        ui.label(index.to_string());
+                        ui.label(evidence.mass.to_string());
+                        ui.label(evidence.delta.to_string());
+                        match evidence.intensity {
+                            Some(intensity) => ui.label(intensity.to_string()),
+                            None => ui
+                                .colored_label(ui.visuals().warn_fg_color, "missing")
+                                .on_hover_text("zero-filled miss"),
+                        };
+                        ui.label(format!("{:.1}%", evidence.relative));
+                        ui.label(
+                            evidence
+                                .neighbours
+                                .iter()
+                                .map(|(mass, intensity)| format!("{mass} ({intensity})"))
+                                .join(" "),
+                        );
+                        ui.label(format!("{:.2}", evidence.contribution));
+                        ui.end_row();
+                    }
+                });
+                // Compare
+                if let Some(rank) = explanation.compare {
+                    let compared = &predictions[rank.min(last)];
+                    ui.separator();
+                    ui.label(compared.score.to_string());
+                    ui.separator();
+                    Grid::new("comparison").striped(true).show(ui, |ui| {
+                        for column in ["Step", "Mass", "Compared mass", "Delta", "Compared delta"] {
+                            ui.strong(column);
+                        }
+                        ui.end_row();
+                        let color = ui.visuals().warn_fg_color;
+                        for (index, diverged) in explanation::divergence(prediction, compared)
+                            .into_iter()
+                            .enumerate()
+                        {
+                            let mut cell = |text: String| {
+                                if diverged {
+                                    ui.colored_label(color, text);
+                                } else {
+                                    ui.label(text);
+                                }
+                            };
+                            let step =
+                                |prediction: &Prediction| prediction.steps.get(index).copied();
+                            let text = |value: Option<usize>| {
+                                value.map_or("-".to_owned(), |value| value.to_string())
+                            };
+                            cell(index.to_string());
+                            cell(text(step(prediction).map(|step| step.mass)));
+                            cell(text(step(compared).map(|step| step.mass)));
+                            cell(text(step(prediction).map(|step| step.delta)));
+                            cell(text(step(compared).map(|step| step.delta)));
+                            ui.end_row();
+                        }
+                    });
+                }
+            });
+    }
 }
 
 impl App {
-    fn plot(&self, ui: &mut Ui) -> Response {
+    /// Plot (inner are the clicked peak mass and the peak mass under the
+    /// pointer)
+    fn plot(&self, ui: &mut Ui) -> InnerResponse<(Option<usize>, Option<usize>)> {
         // let size = TextStyle::Body.resolve(ui.style()).size;
         let size = ui.text_style_height(&TextStyle::Body);
         let mut bar_charts = Vec::new();
@@ -427,12 +1773,7 @@ impl App {
                 .color(Color32::GRAY.linear_multiply(0.1)),
         );
         // Filtered bar chart
-        let peaks = ui.memory_mut(|memory| {
-            memory
-                .caches
-                .cache::<Bounded>()
-                .get((&parsed.peaks, self.bounds))
-        });
+        let peaks = self.peaks(ui.ctx());
         let bars = peaks
             .iter()
             .map(|(&mass, &intensity)| Bar::new(mass as _, intensity as _).name(mass))
@@ -485,22 +1826,23 @@ impl App {
         //         [14, 14]
         //       ]
         //     ]
-        let predictions = ui.memory_mut(|memory| {
-            memory.caches.cache::<Predicted>().get(Key {
-                mass: self.mass,
-                peaks: &peaks,
-                pattern: &self.pattern,
-                zero_is_included: (self.bounds.intensity, Bound::Unbounded).contains(&0),
-            })
-        });
-        for (i, prediction) in predictions.into_iter().take(self.count).enumerate().rev() {
+        let predictions = self.predictions(ui.ctx(), &peaks);
+        let mut scores = HashMap::new();
+        for (i, prediction) in predictions.into_iter().enumerate().rev() {
+            let selected = self.table.selected.contains(&prediction);
+            if i >= self.count && !selected {
+                continue;
+            }
             let color = color(i);
-            let mut series = Vec::with_capacity(prediction.0.ndim());
-            let mut mass = self.mass;
-            for j in 0..prediction.0.ndim() {
-                let delta = self.pattern[j][prediction.0[j]];
-                mass -= delta;
-                let intensity = peaks.get(&mass).copied().unwrap_or_default();
+            let mut score = format!("Anchor: {}\n{}", prediction.anchor, prediction.score);
+            if let Some((feature, step)) = prediction.feature() {
+                write!(score, "\nFeature: {} at {}", feature.label(), step.mass).ok();
+            }
+            scores.insert(format!("Prediction {i}"), score);
+            let mut series = Vec::with_capacity(prediction.steps.len());
+            for (j, step) in prediction.steps.into_iter().enumerate() {
+                let (mass, delta) = (step.mass, step.delta);
+                let intensity = step.intensity.unwrap_or_default();
                 series.push([mass as f64, intensity as f64]);
                 let mut text = String::new();
                 if self.label.contains(Label::Index) {
@@ -512,6 +1854,9 @@ impl App {
                 if self.label.contains(Label::Delta) {
                     writeln!(text, "{delta}").ok();
                 }
+                if let Some(feature) = step.feature {
+                    writeln!(text, "{}", feature.label()).ok();
+                }
                 // let mut job = LayoutJob::default();
                 // job.append(&text, 5.0 * size as f32, default());
                 // // let mut job = LayoutJob::simple(text, default(), color(i), 0.0);
@@ -530,11 +1875,168 @@ impl App {
                 Points::new(series)
                     .color(color)
                     .filled(true)
-                    .radius(size / 2.0)
+                    .radius(if selected { size } else { size / 2.0 })
                     .shape(MarkerShape::Circle)
                     .name(format_args!("Prediction {i}")),
             );
         }
+        // Reference
+        if self.reference {
+            if let Ok(fatty_acid) = self.lipid.parse::<FattyAcid>() {
+                let color = ui.visuals().warn_fg_color;
+                let mut series = Vec::new();
+                for ion in fatty_acid.ions(self.derivative) {
+                    let intensity = peaks.get(&ion.mass).copied().unwrap_or_default() as f64;
+                    series.push([ion.mass as f64, intensity]);
+                    texts.push(
+                        Text::new(
+                            PlotPoint::new(ion.mass as f64, intensity),
+                            RichText::new(ion.label).monospace().size(size),
+                        )
+                        .anchor(Align2::CENTER_TOP)
+                        .color(color)
+                        .name("Reference"),
+                    );
+                }
+                points.push(
+                    Points::new(series)
+                        .color(color)
+                        .radius(size / 2.0)
+                        .shape(MarkerShape::Down)
+                        .name("Reference"),
+                );
+            }
+        }
+        // Isotopes
+        if self.isotopes {
+            if let Ok(formula) = self.proposed() {
+                let isotopes = self.isotopes(ui.ctx(), &formula);
+                let mass = self.monoisotopic(&formula);
+                let fit = isotopes.pattern.fit(&peaks, mass);
+                let shift = mass as f64 - formula.nominal() as f64;
+                let width = if self.fine_structure { 0.05 } else { 0.25 };
+                let bars = isotopes
+                    .peaks
+                    .iter()
+                    .map(|&(mass, abundance)| {
+                        Bar::new(mass + shift, abundance * fit.scale).width(width)
+                    })
+                    .collect();
+                bar_charts.push(
+                    BarChart::new(bars)
+                        .name("Isotopes")
+                        .color(ui.visuals().warn_fg_color),
+                );
+                scores.insert(
+                    "Isotopes".to_owned(),
+                    format!("{formula} at {mass}\nFit: {:.4}", fit.score),
+                );
+            }
+        }
+        // Clusters
+        if self.clusters {
+            let color = ui.visuals().hyperlink_color;
+            let mut series = Vec::new();
+            for cluster in self.clusters(ui.ctx(), &peaks) {
+                let [m, m1, m2] = cluster.intensities.map(|intensity| intensity as f64);
+                series.push([cluster.mass as f64, m]);
+                texts.push(
+                    Text::new(
+                        PlotPoint::new(cluster.mass as f64, m),
+                        RichText::new(cluster.to_string()).monospace().size(size),
+                    )
+                    .anchor(Align2::CENTER_BOTTOM)
+                    .color(color)
+                    .name("Clusters"),
+                );
+                scores
+                    .entry("Clusters".to_owned())
+                    .or_insert_with(String::new)
+                    .push_str(&format!(
+                        "{}: {cluster} (M+1 {:.1}%, M+2 {:.1}%)\n",
+                        cluster.mass,
+                        m1 / m * 100.0,
+                        m2 / m * 100.0,
+                    ));
+            }
+            points.push(
+                Points::new(series)
+                    .color(color)
+                    .radius(size / 2.0)
+                    .shape(MarkerShape::Diamond)
+                    .name("Clusters"),
+            );
+        }
+        // Neutral losses
+        if self.losses.show && self.losses.highlight {
+            let precursor = self.losses.precursor.unwrap_or(self.mass);
+            let color = ui.visuals().selection.bg_fill;
+            let top = peaks.get(&precursor).copied().unwrap_or_default() as f64;
+            for fragment in self.losses.fragments(precursor, &peaks) {
+                if fragment.known.is_empty() {
+                    continue;
+                }
+                let name = format!("Loss of {}", fragment.loss);
+                let intensity = fragment.intensity as f64;
+                lines.push(
+                    plot::Line::new(vec![
+                        [precursor as f64, top],
+                        [fragment.mass as f64, intensity],
+                    ])
+                    .color(color)
+                    .style(LineStyle::dashed_loose())
+                    .name(&name)
+                    .into(),
+                );
+                texts.push(
+                    Text::new(
+                        PlotPoint::new(fragment.mass as f64, intensity),
+                        RichText::new(format!("-{}", fragment.loss))
+                            .monospace()
+                            .size(size),
+                    )
+                    .anchor(Align2::LEFT_BOTTOM)
+                    .color(color)
+                    .name(&name),
+                );
+                scores.insert(name, fragment.known.join("\n"));
+            }
+        }
+        // Ranked candidate
+        if self.ranking.show {
+            let structures = self.ranked(ui.ctx(), &peaks);
+            if let Some(structure) = self
+                .ranking
+                .selected
+                .as_ref()
+                .and_then(|key| structures.iter().find(|structure| &structure.key == key))
+            {
+                let color = ui.visuals().warn_fg_color;
+                let name = format!("Candidate {}", structure.smiles);
+                for (&mass, fragment) in &structure.explained {
+                    let intensity = peaks.get(&mass).copied().unwrap_or_default() as f64;
+                    texts.push(
+                        Text::new(
+                            PlotPoint::new(mass as f64, intensity),
+                            RichText::new(fragment.formula.to_string())
+                                .monospace()
+                                .size(size),
+                        )
+                        .anchor(Align2::CENTER_BOTTOM)
+                        .color(color)
+                        .name(&name),
+                    );
+                }
+                scores.insert(
+                    name,
+                    format!(
+                        "score = {:.3}\ncoverage = {:.1}%",
+                        structure.score,
+                        structure.coverage * 100.0,
+                    ),
+                );
+            }
+        }
         // Limits
         if let Some(value) = self.limits.mass.0 {
             lines.push(VLine::new(value as f64).name("Min mass").into());
@@ -551,6 +2053,11 @@ impl App {
         // Plot
         Plot::new("plot")
             .legend(Legend::default())
+            .label_formatter(move |name, value| match scores.get(name) {
+                Some(score) => format!("{name}\n{score}"),
+                None if name.is_empty() => format!("x = {:.1}\ny = {:.1}", value.x, value.y),
+                None => format!("{name}\nx = {:.1}\ny = {:.1}", value.x, value.y),
+            })
             .coordinates_formatter(Corner::LeftBottom, CoordinatesFormatter::default())
             .show(ui, |plot_ui| {
                 for bar_chart in bar_charts {
@@ -569,8 +2076,126 @@ impl App {
                 for text in texts {
                     plot_ui.text(text);
                 }
+                // Bar under the pointer
+                let pointed = plot_ui.pointer_coordinate().and_then(|point| {
+                    let mass = point.x.round();
+                    let intensity = *peaks.get(&(mass as usize))?;
+                    (mass >= 0.0 && (0.0..=intensity as f64).contains(&point.y))
+                        .then_some(mass as usize)
+                });
+                // Click on a bar
+                (plot_ui.plot_clicked().then_some(pointed).flatten(), pointed)
+            })
+    }
+
+    /// Formula of the isotope pattern (the parsed one if none is proposed)
+    fn proposed(&self) -> Result<Formula, Error> {
+        match self.formula.trim() {
+            "" => self
+                .parsed
+                .get(&0)
+                .map_or("", |parsed| &parsed.formula)
+                .parse(),
+            formula => formula.parse(),
+        }
+    }
+
+    /// Mass of the monoisotopic peak of the formula in the spectrum (the
+    /// molecular ion if found)
+    fn monoisotopic(&self, formula: &Formula) -> usize {
+        match &self.molecular_ion {
+            Some(molecular_ion) => molecular_ion.mass,
+            None => formula.nominal(),
+        }
+    }
+
+    /// Elemental composition candidates of the composition mass
+    fn candidates(&self, ctx: &Context, peaks: &BTreeMap<usize, u64>) -> Vec<Candidate> {
+        ctx.memory_mut(|memory| {
+            memory.caches.cache::<Composed>().get(ComposerKey {
+                composition: &self.composition,
+                peaks,
             })
-            .response
+        })
+    }
+
+    /// Theoretical isotope pattern of the formula
+    fn isotopes(&self, ctx: &Context, formula: &Formula) -> Isotopes {
+        ctx.memory_mut(|memory| {
+            memory
+                .caches
+                .cache::<Patterned>()
+                .get((formula, self.fine_structure))
+        })
+    }
+
+    /// Isotope clusters of the peaks
+    fn clusters(&self, ctx: &Context, peaks: &BTreeMap<usize, u64>) -> Vec<Cluster> {
+        ctx.memory_mut(|memory| memory.caches.cache::<Clustered>().get((peaks, self.noise)))
+    }
+
+    /// Filtered peaks of the first file
+    fn peaks(&self, ctx: &Context) -> BTreeMap<usize, u64> {
+        ctx.memory_mut(|memory| {
+            memory
+                .caches
+                .cache::<Bounded>()
+                .get((&self.parsed[&0].peaks, self.bounds))
+        })
+    }
+
+    fn predictions(&self, ctx: &Context, peaks: &BTreeMap<usize, u64>) -> Vec<Prediction> {
+        ctx.memory_mut(|memory| {
+            memory.caches.cache::<Predicted>().get(Key {
+                mass: self.mass,
+                peaks,
+                pattern: &self.pattern,
+                features: &self.features,
+                zero_is_included: (self.bounds.intensity, Bound::Unbounded).contains(&0),
+                scoring: self.scoring,
+                penalty: self.penalty,
+                direction: self.direction,
+                anchor: self.anchor,
+                threshold: self.anchor_threshold,
+            })
+        })
+    }
+
+    /// Records of the structure library containing the substructure
+    fn found(&self, ctx: &Context) -> Result<Found, String> {
+        ctx.memory_mut(|memory| {
+            memory.caches.cache::<Searched>().get(SearcherKey {
+                query: &self.structures.query,
+                any_element: self.structures.any_element,
+                any_bond: self.structures.any_bond,
+                records: &self.structures.records,
+            })
+        })
+    }
+
+    /// Candidate structures ranked against the peaks
+    fn ranked(&self, ctx: &Context, peaks: &BTreeMap<usize, u64>) -> Vec<Structure> {
+        ctx.memory_mut(|memory| {
+            memory.caches.cache::<Ranked>().get(RankerKey {
+                ranking: &self.ranking,
+                fragmentation: &self.fragmentation,
+                peaks,
+            })
+        })
+    }
+
+    /// Double bond positional isomers ranked against the Finder mass
+    fn isomers(&self, ctx: &Context) -> Result<Vec<Isomer>, String> {
+        let peaks = self.peaks(ctx);
+        ctx.memory_mut(|memory| {
+            memory.caches.cache::<Localized>().get(LocalizerKey {
+                mass: self.mass,
+                peaks: &peaks,
+                chain: self.chain,
+                scoring: self.scoring,
+                penalty: self.penalty,
+            })
+        })
     }
 }
 
@@ -584,11 +2209,21 @@ impl eframe::App for App {
         self.top_panel(ctx, frame);
         self.bottom_panel(ctx);
         self.left_panel(ctx);
+        self.right_panel(ctx);
         self.central_panel(ctx);
         // self.windows(ctx);
         self.drag_and_drop_files(ctx);
         self.errors(ctx);
         self.files(ctx);
+        self.table(ctx);
+        self.explanation(ctx);
+        self.composition(ctx);
+        self.losses(ctx);
+        self.differences(ctx);
+        self.structures(ctx);
+        self.drawing(ctx);
+        self.fragmentation(ctx);
+        self.ranking(ctx);
     }
 }
 
@@ -633,6 +2268,30 @@ impl Default for Bounds {
     }
 }
 
+/// Structures
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Structures {
+    show: bool,
+    /// Substructure query (SMILES)
+    query: String,
+    /// The query atoms match any element
+    any_element: bool,
+    /// The query bonds match any bond
+    any_bond: bool,
+    #[serde(skip)]
+    records: Vec<Record>,
+}
+
+/// Molecule drawing
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Drawing {
+    show: bool,
+    #[serde(skip)]
+    editor: Editor,
+}
+
 /// Errors
 #[derive(Debug, Default)]
 struct Errors {
@@ -671,7 +2330,21 @@ struct Statistics {
 }
 
 mod bounder;
+mod cluster;
+mod composer;
+mod difference;
+mod explanation;
+mod feature;
+mod fragmenter;
+mod localizer;
+mod loss;
+mod molecular_ion;
+mod patterner;
 mod predictioner;
+mod ranker;
+mod scoring;
+mod searcher;
+mod table;
 
 #[cfg(test)]
 mod test {
@@ -0,0 +1,127 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+};
+
+/// Losses from the molecular ion that are (almost) never observed
+const IMPLAUSIBLE_LOSSES: [RangeInclusive<usize>; 2] = [4..=13, 21..=25];
+
+/// Molecular ion candidate
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct MolecularIon {
+    pub(super) mass: usize,
+    pub(super) intensity: u64,
+    pub(super) warnings: Vec<Warning>,
+}
+
+impl MolecularIon {
+    /// Highest mass peak above the noise `threshold` (in percent of the base
+    /// peak) that isn't an isotope peak of a lighter one.
+    pub(super) fn find(
+        peaks: &BTreeMap<usize, u64>,
+        threshold: u8,
+        mw: Option<u64>,
+    ) -> Option<Self> {
+        let base = peaks.values().copied().max()?;
+        let threshold = base * threshold as u64 / 100;
+        let significant = |mass: usize| {
+            peaks
+                .get(&mass)
+                .copied()
+                .filter(|&intensity| intensity >= threshold && intensity > 0)
+        };
+        let (&mass, &intensity) = peaks.iter().rev().find(|(&mass, &intensity)| {
+            intensity >= threshold
+                && intensity > 0
+                && (1..=2).all(|offset| {
+                    mass.checked_sub(offset)
+                        .and_then(significant)
+                        .into_iter()
+                        .all(|lighter| lighter <= intensity)
+                })
+        })?;
+        let mut warnings = Vec::new();
+        if mass % 2 == 1 {
+            warnings.push(Warning::OddNitrogen);
+        }
+        for loss in IMPLAUSIBLE_LOSSES.into_iter().flatten() {
+            if let Some(fragment) = mass
+                .checked_sub(loss)
+                .filter(|&fragment| significant(fragment).is_some())
+            {
+                warnings.push(Warning::ImplausibleLoss { loss, fragment });
+            }
+        }
+        if let Some(mw) = mw.filter(|&mw| mw != mass as u64) {
+            warnings.push(Warning::MolecularWeight(mw));
+        }
+        Some(Self {
+            mass,
+            intensity,
+            warnings,
+        })
+    }
+}
+
+/// Warning
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum Warning {
+    /// Odd mass: odd number of nitrogen atoms (nitrogen rule)
+    OddNitrogen,
+    /// Significant fragment at an implausible loss from the candidate
+    ImplausibleLoss { loss: usize, fragment: usize },
+    /// Candidate disagrees with the `MW` field
+    MolecularWeight(u64),
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::OddNitrogen => f.write_str("odd mass: odd number of nitrogen atoms"),
+            Self::ImplausibleLoss { loss, fragment } => {
+                write!(f, "implausible loss of {loss} to {fragment}")
+            }
+            Self::MolecularWeight(mw) => write!(f, "MW field is {mw}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn isotopes() {
+        // 345 is the molecular ion, 346 and 347 its isotope peaks, 348 noise
+        let peaks = BTreeMap::from([
+            (92, 999),
+            (108, 700),
+            (330, 40),
+            (345, 120),
+            (346, 30),
+            (347, 5),
+            (348, 1),
+        ]);
+        let ion = MolecularIon::find(&peaks, 1, None).unwrap();
+        assert_eq!(ion.mass, 345);
+        assert_eq!(ion.warnings, [Warning::OddNitrogen]);
+    }
+
+    #[test]
+    fn warnings() {
+        let peaks = BTreeMap::from([(100, 999), (290, 50), (300, 100)]);
+        let ion = MolecularIon::find(&peaks, 1, Some(302)).unwrap();
+        assert_eq!(ion.mass, 300);
+        assert_eq!(
+            ion.warnings,
+            [
+                Warning::ImplausibleLoss {
+                    loss: 10,
+                    fragment: 290
+                },
+                Warning::MolecularWeight(302),
+            ]
+        );
+    }
+}
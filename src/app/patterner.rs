@@ -0,0 +1,46 @@
+//! Theoretical isotope pattern of the proposed formula, shaped for the
+//! overlay.
+
+use crate::{formula::Formula, isotope::Pattern};
+use egui::util::cache::{ComputerMut, FrameCache};
+
+/// Patterned
+pub(super) type Patterned = FrameCache<Isotopes, Patterner>;
+
+/// Patterner
+#[derive(Default)]
+pub(super) struct Patterner;
+
+impl ComputerMut<(&Formula, bool), Isotopes> for Patterner {
+    fn compute(&mut self, (formula, fine_structure): (&Formula, bool)) -> Isotopes {
+        let pattern = Pattern::new(formula);
+        let peaks = if fine_structure {
+            let max = pattern.peaks.iter().map(|peak| peak.1).fold(0.0, f64::max);
+            pattern
+                .peaks
+                .iter()
+                .map(|&(mass, abundance)| {
+                    let mass = mass - formula.monoisotopic() + formula.nominal() as f64;
+                    (mass, abundance / max)
+                })
+                .collect()
+        } else {
+            pattern
+                .nominal()
+                .into_iter()
+                .map(|(mass, abundance)| (mass as f64, abundance))
+                .collect()
+        };
+        Isotopes { pattern, peaks }
+    }
+}
+
+/// Isotope pattern
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct Isotopes {
+    pub(super) pattern: Pattern,
+    /// Peaks of the overlay: the fine structure or the nominal pattern (the
+    /// monoisotopic peak at the nominal mass, relative to the most abundant
+    /// peak)
+    pub(super) peaks: Vec<(f64, f64)>,
+}
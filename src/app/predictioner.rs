@@ -1,7 +1,15 @@
+use super::{
+    feature::Feature,
+    scoring::{Score, Scoring},
+};
 use egui::util::cache::{ComputerMut, FrameCache};
-use indexmap::IndexMap;
-use ndarray::{indices, Dim, Dimension, IxDynImpl};
-use std::{collections::BTreeMap, iter::zip};
+use ndarray::{indices, Dimension};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    iter::zip,
+};
 
 /// Key
 #[derive(Clone, Copy, Debug, Hash)]
@@ -9,36 +17,253 @@ pub(super) struct Key<'a> {
     pub(super) mass: usize,
     pub(super) peaks: &'a BTreeMap<usize, u64>,
     pub(super) pattern: &'a [Vec<usize>],
+    /// Feature templates searched alongside the plain pattern
+    pub(super) features: &'a [Feature],
     pub(super) zero_is_included: bool,
+    pub(super) scoring: Scoring,
+    pub(super) penalty: u8,
+    pub(super) direction: Direction,
+    pub(super) anchor: Anchor,
+    pub(super) threshold: u64,
+}
+
+impl Key<'_> {
+    /// Start masses
+    fn anchors(&self) -> Vec<usize> {
+        match self.anchor {
+            Anchor::Mass => vec![self.mass],
+            Anchor::Any => self
+                .peaks
+                .iter()
+                .filter_map(|(&mass, &intensity)| (intensity >= self.threshold).then_some(mass))
+                .collect(),
+        }
+    }
 }
 
 /// Predicted
-pub(super) type Predicted = FrameCache<IndexMap<Dim<IxDynImpl>, f64>, Predictioner>;
+pub(super) type Predicted = FrameCache<Vec<Prediction>, Predictioner>;
 
 /// Predictioner
 #[derive(Default)]
 pub(super) struct Predictioner;
 
-impl ComputerMut<Key<'_>, IndexMap<Dim<IxDynImpl>, f64>> for Predictioner {
-    fn compute(&mut self, args: Key) -> IndexMap<Dim<IxDynImpl>, f64> {
-        let shape = args.pattern.iter().map(Vec::len).collect::<Vec<_>>();
-        let mut predictions = indices(shape)
-            .into_iter()
-            .filter_map(|index| {
-                let mut mass = args.mass;
-                let mut intensity = 0.0;
-                for delta in zip(args.pattern, index.slice()).map(|(step, &index)| step[index]) {
-                    mass = mass.checked_sub(delta)?;
-                    intensity +=
-                        args.peaks
-                            .get(&mass)
-                            .copied()
-                            .or(args.zero_is_included.then_some(0))? as f64;
-                }
-                Some((index, intensity))
-            })
-            .collect::<IndexMap<_, _>>();
-        predictions.sort_by(|_, left, _, right| right.total_cmp(left));
+impl ComputerMut<Key<'_>, Vec<Prediction>> for Predictioner {
+    fn compute(&mut self, args: Key) -> Vec<Prediction> {
+        let mut patterns = vec![(args.pattern.to_vec(), None)];
+        for (index, &feature) in args.features.iter().enumerate() {
+            // The patterns of a signature once (its features are annotated
+            // together)
+            if args.features[..index]
+                .iter()
+                .any(|other| other.signature() == feature.signature())
+            {
+                continue;
+            }
+            patterns.extend(
+                feature
+                    .patterns(args.pattern)
+                    .into_iter()
+                    .map(|(pattern, marks)| (pattern, Some((feature, marks)))),
+            );
+        }
+        let base = args.peaks.values().copied().max().unwrap_or_default();
+        let admits_missing = args.zero_is_included || args.scoring.admits_missing();
+        let mut predictions = Vec::new();
+        for anchor in args.anchors() {
+            for (pattern, feature) in &patterns {
+                let shape = pattern.iter().map(Vec::len).collect::<Vec<_>>();
+                predictions.extend(indices(shape).into_iter().filter_map(|index| {
+                    let mut mass = anchor;
+                    let mut steps = Vec::with_capacity(index.ndim());
+                    for (position, delta) in zip(pattern, index.slice())
+                        .map(|(step, &index)| step[index])
+                        .enumerate()
+                    {
+                        mass = args.direction.step(mass, delta)?;
+                        let intensity = args.peaks.get(&mass).copied();
+                        if intensity.is_none() && !admits_missing {
+                            return None;
+                        }
+                        steps.push(Step {
+                            mass,
+                            delta,
+                            intensity,
+                            feature: feature
+                                .as_ref()
+                                .and_then(|(feature, marks)| marks[position].then_some(*feature)),
+                        });
+                    }
+                    let intensities = steps.iter().map(|step| step.intensity).collect::<Vec<_>>();
+                    Some(Prediction {
+                        anchor,
+                        steps,
+                        score: args.scoring.score(&intensities, base, args.penalty),
+                    })
+                }));
+            }
+        }
+        predictions.sort_by(|left, right| right.score.value.total_cmp(&left.score.value));
         predictions
     }
 }
+
+/// Prediction
+#[derive(Clone, Debug)]
+pub(super) struct Prediction {
+    pub(super) anchor: usize,
+    pub(super) steps: Vec<Step>,
+    pub(super) score: Score,
+}
+
+impl Prediction {
+    /// Feature implied by the path and the step ending its signature
+    pub(super) fn feature(&self) -> Option<(Feature, &Step)> {
+        self.steps
+            .iter()
+            .rev()
+            .find_map(|step| Some((step.feature?, step)))
+    }
+}
+
+/// Step
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Step {
+    pub(super) mass: usize,
+    pub(super) delta: usize,
+    /// Intensity of the matched peak (`None` if the step is missing)
+    pub(super) intensity: Option<u64>,
+    /// Feature of the template the step belongs to
+    pub(super) feature: Option<Feature>,
+}
+
+/// Direction
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(super) enum Direction {
+    /// Walk down from the anchor (subtract deltas)
+    #[default]
+    Backward,
+    /// Walk up from the anchor (add deltas)
+    Forward,
+}
+
+impl Direction {
+    pub(super) const ALL: [Self; 2] = [Self::Backward, Self::Forward];
+
+    fn step(&self, mass: usize, delta: usize) -> Option<usize> {
+        match self {
+            Self::Backward => mass.checked_sub(delta),
+            Self::Forward => mass.checked_add(delta),
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Backward => "Backward",
+            Self::Forward => "Forward",
+        })
+    }
+}
+
+/// Anchor
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(super) enum Anchor {
+    /// Start from the input mass
+    #[default]
+    Mass,
+    /// Start from every peak above the threshold
+    Any,
+}
+
+impl Anchor {
+    pub(super) const ALL: [Self; 2] = [Self::Mass, Self::Any];
+}
+
+impl Display for Anchor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Mass => "Mass",
+            Self::Any => "Any",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key<'a>(peaks: &'a BTreeMap<usize, u64>, pattern: &'a [Vec<usize>]) -> Key<'a> {
+        Key {
+            mass: 0,
+            peaks,
+            pattern,
+            features: &[],
+            zero_is_included: false,
+            scoring: Scoring::Sum,
+            penalty: 0,
+            direction: Direction::Backward,
+            anchor: Anchor::Mass,
+            threshold: 0,
+        }
+    }
+
+    #[test]
+    fn forward() {
+        let peaks = BTreeMap::from([(92, 999), (106, 50), (120, 40), (132, 30), (134, 5)]);
+        let pattern = [vec![14], vec![14], vec![12, 14]];
+        let predictions = Predictioner.compute(Key {
+            mass: 92,
+            direction: Direction::Forward,
+            ..key(&peaks, &pattern)
+        });
+        let masses = predictions[0]
+            .steps
+            .iter()
+            .map(|step| step.mass)
+            .collect::<Vec<_>>();
+        assert_eq!(masses, [106, 120, 132]);
+        assert_eq!(predictions.len(), 2);
+    }
+
+    #[test]
+    fn any() {
+        let peaks = BTreeMap::from([(50, 1), (64, 100), (78, 100), (200, 1), (214, 2)]);
+        let pattern = [vec![14]];
+        let predictions = Predictioner.compute(Key {
+            direction: Direction::Forward,
+            anchor: Anchor::Any,
+            threshold: 1,
+            ..key(&peaks, &pattern)
+        });
+        let anchors = predictions
+            .iter()
+            .map(|prediction| prediction.anchor)
+            .collect::<Vec<_>>();
+        assert_eq!(anchors, [50, 64, 200]);
+    }
+
+    #[test]
+    fn feature() {
+        // Methyl branch: 28 gap instead of 14 between 218 and 246
+        let peaks = BTreeMap::from([(274, 60), (260, 50), (246, 40), (218, 30), (204, 20)]);
+        let pattern = [vec![14], vec![14], vec![14], vec![14]];
+        let predictions = Predictioner.compute(Key {
+            mass: 274,
+            features: &[Feature::MethylBranch],
+            ..key(&peaks, &pattern)
+        });
+        let (feature, step) = predictions[0].feature().unwrap();
+        assert_eq!(feature, Feature::MethylBranch);
+        assert_eq!(step.mass, 218);
+        assert_eq!(predictions.len(), 1);
+        // An oxo group has the same signature: searched once
+        let predictions = Predictioner.compute(Key {
+            mass: 274,
+            features: &[Feature::MethylBranch, Feature::Oxo],
+            ..key(&peaks, &pattern)
+        });
+        assert_eq!(predictions.len(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Candidate structures ranked by the peaks their in-silico fragments explain.
+//!
+//! The score is the mean of the fraction of the explained peaks (above the
+//! threshold) and of the fraction of the intensity they cover; the weighted
+//! cosine similarity to the predicted spectrum is kept to break the ties.
+
+use super::fragmenter::{similarity, spectrum, Fragment, Fragmentation};
+use crate::widget::{canonical::Key, molecule::Molecule};
+use egui::util::cache::{ComputerMut, FrameCache};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    hash::{Hash, Hasher},
+};
+
+/// Ranked
+pub(super) type Ranked = FrameCache<Vec<Structure>, Ranker>;
+
+/// Ranker key
+#[derive(Clone, Copy, Debug)]
+pub(super) struct RankerKey<'a> {
+    pub(super) ranking: &'a Ranking,
+    pub(super) fragmentation: &'a Fragmentation,
+    pub(super) peaks: &'a BTreeMap<usize, u64>,
+}
+
+/// Hash of the settings the ranks depend on (not the shown or selected
+/// ones)
+impl Hash for RankerKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ranking.candidates.hash(state);
+        self.ranking.threshold.hash(state);
+        self.fragmentation.depth.hash(state);
+        self.fragmentation.hydrogen_rearrangements.hash(state);
+        self.fragmentation.mclafferty.hash(state);
+        self.fragmentation.alpha_cleavage.hash(state);
+        self.peaks.hash(state);
+    }
+}
+
+/// Ranker
+#[derive(Default)]
+pub(super) struct Ranker;
+
+impl ComputerMut<RankerKey<'_>, Vec<Structure>> for Ranker {
+    fn compute(&mut self, args: RankerKey) -> Vec<Structure> {
+        args.ranking.rank(args.fragmentation, args.peaks)
+    }
+}
+
+/// Ranking settings
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub(super) struct Ranking {
+    pub(super) show: bool,
+    /// Candidates (SMILES, one per line)
+    pub(super) candidates: String,
+    /// Formula of the isomers to add to the candidates
+    pub(super) isomers: String,
+    /// Relative intensity (‰ of the base peak) below which the peaks needn't
+    /// be explained
+    pub(super) threshold: u64,
+    /// Candidate annotated on the plot (by its canonical key)
+    #[serde(skip)]
+    pub(super) selected: Option<Key>,
+}
+
+impl Ranking {
+    /// Candidates (the isomorphic duplicates and the invalid SMILES skipped)
+    pub(super) fn molecules(&self) -> Vec<(String, Key, Molecule)> {
+        let mut keys = HashSet::<Key>::new();
+        self.candidates
+            .lines()
+            .map(str::trim)
+            .filter(|smiles| !smiles.is_empty())
+            .filter_map(|smiles| {
+                let molecule = smiles.parse::<Molecule>().ok()?;
+                let key = molecule.key();
+                keys.insert(key.clone())
+                    .then(|| (smiles.to_owned(), key, molecule))
+            })
+            .collect()
+    }
+
+    /// Candidates from the best fitting
+    pub(super) fn rank(
+        &self,
+        fragmentation: &Fragmentation,
+        peaks: &BTreeMap<usize, u64>,
+    ) -> Vec<Structure> {
+        let base = peaks.values().copied().max().unwrap_or_default();
+        let observed = peaks
+            .iter()
+            .filter(|(_, &intensity)| intensity * 1000 >= base * self.threshold)
+            .map(|(&mass, &intensity)| (mass, intensity))
+            .collect::<BTreeMap<_, _>>();
+        let total = observed.values().sum::<u64>();
+        let mut structures = self
+            .molecules()
+            .into_iter()
+            .map(|(smiles, key, molecule)| {
+                let fragments = fragmentation.fragments(&molecule);
+                let mut explained = BTreeMap::<usize, Fragment>::new();
+                for fragment in fragments
+                    .iter()
+                    .filter(|fragment| observed.contains_key(&fragment.mass))
+                {
+                    match explained.get(&fragment.mass) {
+                        Some(known) if known.weight >= fragment.weight => {}
+                        _ => {
+                            explained.insert(fragment.mass, fragment.clone());
+                        }
+                    }
+                }
+                let covered = explained.keys().map(|mass| observed[mass]).sum::<u64>();
+                let fraction = ratio(explained.len() as _, observed.len() as _);
+                let coverage = ratio(covered as _, total as _);
+                let predicted = spectrum(&smiles, &fragments);
+                Structure {
+                    score: (fraction + coverage) / 2.0,
+                    coverage,
+                    similarity: similarity(peaks, &predicted.peaks),
+                    smiles,
+                    key,
+                    formula: predicted.formula,
+                    explained,
+                    peaks: observed.len(),
+                }
+            })
+            .collect::<Vec<_>>();
+        structures.sort_by_key(|structure| {
+            (
+                Reverse(ordered(structure.score)),
+                Reverse(ordered(structure.similarity)),
+            )
+        });
+        structures
+    }
+}
+
+/// Ranked candidate structure
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Structure {
+    pub(super) smiles: String,
+    pub(super) key: Key,
+    pub(super) formula: String,
+    pub(super) score: f64,
+    /// Fraction of the observed intensity explained
+    pub(super) coverage: f64,
+    pub(super) similarity: f64,
+    /// Most favored fragments of the explained peaks
+    pub(super) explained: BTreeMap<usize, Fragment>,
+    /// Number of the peaks to explain
+    pub(super) peaks: usize,
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        return 0.0;
+    }
+    numerator / denominator
+}
+
+fn ordered(value: f64) -> i64 {
+    (value * 1e6) as i64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rank() {
+        // Butanal: the McLafferty ion (44) that 2-butanone lacks
+        let peaks = BTreeMap::from([(29, 500), (44, 999), (72, 200)]);
+        let ranking = Ranking {
+            candidates: "CCC(C)=O\nCCCC=O\nO=CCCC\n\nnot SMILES".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(ranking.molecules().len(), 2);
+        let fragmentation = Fragmentation {
+            hydrogen_rearrangements: false,
+            ..Default::default()
+        };
+        let structures = ranking.rank(&fragmentation, &peaks);
+        assert_eq!(structures.len(), 2);
+        assert_eq!(structures[0].smiles, "CCCC=O");
+        assert_eq!(structures[0].formula, "C4H8O");
+        assert_eq!(structures[0].explained.len(), 3);
+        assert_eq!(structures[0].coverage, 1.0);
+        assert!(structures[0].score > structures[1].score);
+    }
+}
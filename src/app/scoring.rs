@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter, Write};
+
+/// Scoring
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(super) enum Scoring {
+    /// Sum of the step intensities
+    #[default]
+    Sum,
+    /// Arithmetic mean of the step intensities
+    Mean,
+    /// Geometric mean of the step intensities
+    GeometricMean,
+    /// Sum of the logarithms of the step intensities
+    LogSum,
+    /// Count of the matched steps
+    Count,
+    /// Sum of the step intensities with a penalty for each missing step
+    Penalized,
+    /// Mean ratio of the neighbouring step intensities
+    Continuity,
+}
+
+impl Scoring {
+    pub(super) const ALL: [Self; 7] = [
+        Self::Sum,
+        Self::Mean,
+        Self::GeometricMean,
+        Self::LogSum,
+        Self::Count,
+        Self::Penalized,
+        Self::Continuity,
+    ];
+
+    /// Missing steps are admitted without the zero intensity bound
+    pub(super) fn admits_missing(&self) -> bool {
+        matches!(self, Self::Penalized)
+    }
+
+    /// Score the step intensities (`None` is a missing step).
+    ///
+    /// `penalty` is the cost of a missing step in percent of the `base` (peak)
+    /// intensity.
+    pub(super) fn score(&self, intensities: &[Option<u64>], base: u64, penalty: u8) -> Score {
+        let count = intensities.len() as f64;
+        let intensity = |index: usize| intensities[index].unwrap_or_default() as f64;
+        let terms = (0..intensities.len())
+            .map(|index| match self {
+                Self::Sum => intensity(index),
+                Self::Mean => intensity(index) / count,
+                Self::GeometricMean => intensity(index).ln() / count,
+                Self::LogSum => intensity(index).ln_1p(),
+                Self::Count => intensities[index].map_or(0.0, |_| 1.0),
+                Self::Penalized => intensities[index].map_or_else(
+                    || -(base as f64) * penalty as f64 / 100.0,
+                    |intensity| intensity as f64,
+                ),
+                Self::Continuity if index == 0 => 0.0,
+                Self::Continuity => {
+                    let (previous, current) = (intensity(index - 1), intensity(index));
+                    let max = previous.max(current);
+                    if max == 0.0 {
+                        0.0
+                    } else {
+                        previous.min(current) / max / (count - 1.0)
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        let sum = terms.iter().sum::<f64>();
+        let value = match self {
+            Self::GeometricMean if terms.is_empty() => 0.0,
+            Self::GeometricMean => sum.exp(),
+            Self::Continuity if terms.len() == 1 => intensities[0].map_or(0.0, |_| 1.0),
+            _ => sum,
+        };
+        Score {
+            scoring: *self,
+            value,
+            terms,
+        }
+    }
+}
+
+impl Display for Scoring {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Sum => "Sum",
+            Self::Mean => "Mean",
+            Self::GeometricMean => "Geometric mean",
+            Self::LogSum => "Log sum",
+            Self::Count => "Count",
+            Self::Penalized => "Penalized",
+            Self::Continuity => "Continuity",
+        })
+    }
+}
+
+/// Score
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct Score {
+    pub(super) scoring: Scoring,
+    pub(super) value: f64,
+    /// Contribution of each step
+    pub(super) terms: Vec<f64>,
+}
+
+impl Display for Score {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.scoring {
+            Scoring::GeometricMean => write!(f, "{}: exp(", self.scoring)?,
+            _ => write!(f, "{}: ", self.scoring)?,
+        }
+        for (index, term) in self.terms.iter().enumerate() {
+            if index != 0 {
+                f.write_str(" + ")?;
+            }
+            write!(f, "{term:.2}")?;
+        }
+        if let Scoring::GeometricMean = self.scoring {
+            f.write_char(')')?;
+        }
+        write!(f, " = {:.2}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sum() {
+        let score = Scoring::Sum.score(&[Some(10), Some(20), Some(30)], 30, 0);
+        assert_eq!(score.value, 60.0);
+        assert_eq!(score.terms, [10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn geometric_mean() {
+        let score = Scoring::GeometricMean.score(&[Some(4), Some(16)], 16, 0);
+        assert!((score.value - 8.0).abs() < 1e-9);
+        let score = Scoring::GeometricMean.score(&[Some(4), None], 16, 0);
+        assert_eq!(score.value, 0.0);
+    }
+
+    #[test]
+    fn penalized() {
+        let score = Scoring::Penalized.score(&[Some(50), None, Some(30)], 200, 10);
+        assert_eq!(score.terms, [50.0, -20.0, 30.0]);
+        assert_eq!(score.value, 60.0);
+    }
+
+    #[test]
+    fn continuity() {
+        let smooth = Scoring::Continuity.score(&[Some(10), Some(10), Some(10)], 10, 0);
+        assert!((smooth.value - 1.0).abs() < 1e-9);
+        let rough = Scoring::Continuity.score(&[Some(1000), Some(10), Some(1000)], 1000, 0);
+        assert!(rough.value < smooth.value);
+    }
+
+    #[test]
+    fn count() {
+        let score = Scoring::Count.score(&[Some(10), None, Some(0)], 10, 0);
+        assert_eq!(score.value, 2.0);
+    }
+}
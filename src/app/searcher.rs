@@ -0,0 +1,74 @@
+//! Structures of the library containing a substructure.
+
+use crate::widget::{
+    molecule::Molecule,
+    molfile::Record,
+    substructure::{filter, Query},
+};
+use egui::util::cache::{ComputerMut, FrameCache};
+use std::hash::{Hash, Hasher};
+
+/// Searched
+pub(super) type Searched = FrameCache<Result<Found, String>, Searcher>;
+
+/// Searcher key
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SearcherKey<'a> {
+    /// Substructure (SMILES, empty for all)
+    pub(super) query: &'a str,
+    pub(super) any_element: bool,
+    pub(super) any_bond: bool,
+    pub(super) records: &'a [Record],
+}
+
+impl Hash for SearcherKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.query.hash(state);
+        self.any_element.hash(state);
+        self.any_bond.hash(state);
+        for record in self.records {
+            record.molecule.hash(state);
+        }
+    }
+}
+
+/// Searcher
+#[derive(Default)]
+pub(super) struct Searcher;
+
+impl ComputerMut<SearcherKey<'_>, Result<Found, String>> for Searcher {
+    fn compute(&mut self, args: SearcherKey) -> Result<Found, String> {
+        let smiles = args.query.trim();
+        if smiles.is_empty() {
+            return Ok(Found {
+                query: None,
+                records: (0..args.records.len()).collect(),
+            });
+        }
+        let molecule = smiles
+            .parse::<Molecule>()
+            .map_err(|error| error.to_string())?;
+        let mut query = Query::from(&molecule);
+        if args.any_element {
+            query = query.any_element();
+        }
+        if args.any_bond {
+            query = query.any_bond();
+        }
+        let records = filter(args.records, &query)
+            .map(|(index, _)| index)
+            .collect();
+        Ok(Found {
+            query: Some(query),
+            records,
+        })
+    }
+}
+
+/// Search result
+#[derive(Clone, Debug)]
+pub(super) struct Found {
+    pub(super) query: Option<Query>,
+    /// Indices of the records containing the query
+    pub(super) records: Vec<usize>,
+}
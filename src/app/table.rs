@@ -0,0 +1,277 @@
+use super::predictioner::Prediction;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt::{self, Display, Formatter, Write},
+};
+
+/// Table
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub(super) struct Table {
+    pub(super) show: bool,
+    pub(super) sort: Sort,
+    /// Anchors and step masses of the selected predictions (not their ranks,
+    /// which change with the peaks, the pattern and the scoring)
+    #[serde(skip)]
+    pub(super) selected: Selection,
+}
+
+impl Table {
+    /// Ranks in the display order
+    pub(super) fn order(&self, predictions: &[Prediction]) -> Vec<usize> {
+        let mut ranks = (0..predictions.len()).collect::<Vec<_>>();
+        ranks.sort_by(|&left, &right| {
+            let ordering =
+                self.sort
+                    .column
+                    .cmp(left, &predictions[left], right, &predictions[right]);
+            if self.sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        ranks
+    }
+}
+
+/// Selected predictions
+#[derive(Clone, Debug, Default)]
+pub(super) struct Selection(HashSet<(usize, Vec<usize>)>);
+
+impl Selection {
+    /// Whether the prediction is selected
+    pub(super) fn contains(&self, prediction: &Prediction) -> bool {
+        self.0.contains(&Self::key(prediction))
+    }
+
+    /// Select the prediction, or deselect it if it is already selected
+    pub(super) fn toggle(&mut self, prediction: &Prediction) {
+        let key = Self::key(prediction);
+        if !self.0.remove(&key) {
+            self.0.insert(key);
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Anchor and step masses identifying the prediction
+    fn key(prediction: &Prediction) -> (usize, Vec<usize>) {
+        (
+            prediction.anchor,
+            prediction.steps.iter().map(|step| step.mass).collect(),
+        )
+    }
+}
+
+/// Sort
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(super) struct Sort {
+    pub(super) column: Column,
+    pub(super) descending: bool,
+}
+
+impl Sort {
+    /// Sort by the column, toggling the order if it is already sorted by it
+    pub(super) fn toggle(&mut self, column: Column) {
+        if self.column == column {
+            self.descending = !self.descending;
+        } else {
+            self.column = column;
+            self.descending = false;
+        }
+    }
+}
+
+/// Column
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(super) enum Column {
+    #[default]
+    Rank,
+    Score,
+    Anchor,
+    Masses,
+    Deltas,
+    Intensities,
+}
+
+impl Column {
+    pub(super) const ALL: [Self; 6] = [
+        Self::Rank,
+        Self::Score,
+        Self::Anchor,
+        Self::Masses,
+        Self::Deltas,
+        Self::Intensities,
+    ];
+
+    fn cmp(&self, left: usize, lhs: &Prediction, right: usize, rhs: &Prediction) -> Ordering {
+        match self {
+            Self::Rank => left.cmp(&right),
+            Self::Score => lhs.score.value.total_cmp(&rhs.score.value),
+            Self::Anchor => lhs.anchor.cmp(&rhs.anchor),
+            Self::Masses => Iterator::cmp(
+                lhs.steps.iter().map(|step| step.mass),
+                rhs.steps.iter().map(|step| step.mass),
+            ),
+            Self::Deltas => Iterator::cmp(
+                lhs.steps.iter().map(|step| step.delta),
+                rhs.steps.iter().map(|step| step.delta),
+            ),
+            Self::Intensities => Iterator::cmp(
+                lhs.steps.iter().map(|step| step.intensity),
+                rhs.steps.iter().map(|step| step.intensity),
+            ),
+        }
+    }
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rank => "Rank",
+            Self::Score => "Score",
+            Self::Anchor => "Anchor",
+            Self::Masses => "Masses",
+            Self::Deltas => "Deltas",
+            Self::Intensities => "Intensities",
+        })
+    }
+}
+
+/// Row
+#[derive(Clone, Debug, Serialize)]
+struct Row {
+    rank: usize,
+    score: f64,
+    anchor: usize,
+    masses: Vec<usize>,
+    deltas: Vec<usize>,
+    intensities: Vec<Option<u64>>,
+}
+
+impl Row {
+    fn new(rank: usize, prediction: &Prediction) -> Self {
+        Self {
+            rank,
+            score: prediction.score.value,
+            anchor: prediction.anchor,
+            masses: prediction.steps.iter().map(|step| step.mass).collect(),
+            deltas: prediction.steps.iter().map(|step| step.delta).collect(),
+            intensities: prediction.steps.iter().map(|step| step.intensity).collect(),
+        }
+    }
+}
+
+/// CSV with a mass, delta and intensity column triple per step (a missing
+/// intensity is an empty field)
+pub(super) fn csv(predictions: &[Prediction], ranks: &[usize]) -> String {
+    let mut csv = String::from("rank,score,anchor");
+    let steps = predictions
+        .iter()
+        .map(|prediction| prediction.steps.len())
+        .max()
+        .unwrap_or_default();
+    for step in 1..=steps {
+        write!(csv, ",mass_{step},delta_{step},intensity_{step}").ok();
+    }
+    for &rank in ranks {
+        let row = Row::new(rank, &predictions[rank]);
+        write!(csv, "\n{},{},{}", row.rank, row.score, row.anchor).ok();
+        for index in 0..row.masses.len() {
+            write!(csv, ",{},{},", row.masses[index], row.deltas[index]).ok();
+            if let Some(intensity) = row.intensities[index] {
+                write!(csv, "{intensity}").ok();
+            }
+        }
+    }
+    csv
+}
+
+/// JSON array of rows
+pub(super) fn json(predictions: &[Prediction], ranks: &[usize]) -> Result<String> {
+    let rows = ranks
+        .iter()
+        .map(|&rank| Row::new(rank, &predictions[rank]))
+        .collect::<Vec<_>>();
+    Ok(serde_json::to_string_pretty(&rows)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::{
+        predictioner::Step,
+        scoring::{Score, Scoring},
+    };
+
+    fn prediction(anchor: usize, value: f64, steps: &[(usize, usize, Option<u64>)]) -> Prediction {
+        Prediction {
+            anchor,
+            steps: steps
+                .iter()
+                .map(|&(mass, delta, intensity)| Step {
+                    mass,
+                    delta,
+                    intensity,
+                    feature: None,
+                })
+                .collect(),
+            score: Score {
+                scoring: Scoring::Sum,
+                value,
+                terms: Vec::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn order() {
+        let predictions = [
+            prediction(300, 30.0, &[(286, 14, Some(30))]),
+            prediction(100, 20.0, &[(86, 14, Some(20))]),
+        ];
+        let mut table = Table::default();
+        assert_eq!(table.order(&predictions), [0, 1]);
+        table.sort.toggle(Column::Anchor);
+        assert_eq!(table.order(&predictions), [1, 0]);
+        table.sort.toggle(Column::Anchor);
+        assert_eq!(table.order(&predictions), [0, 1]);
+    }
+
+    #[test]
+    fn select() {
+        let mut selection = Selection::default();
+        let selected = prediction(300, 30.0, &[(286, 14, Some(30))]);
+        selection.toggle(&selected);
+        assert!(selection.contains(&selected));
+        // Another prediction at the same rank
+        assert!(!selection.contains(&prediction(300, 30.0, &[(288, 12, Some(30))])));
+        // Rescored
+        assert!(selection.contains(&prediction(300, 10.0, &[(286, 14, None)])));
+        selection.toggle(&selected);
+        assert!(!selection.contains(&selected));
+    }
+
+    #[test]
+    fn export() {
+        let predictions = [prediction(
+            300,
+            30.0,
+            &[(286, 14, Some(30)), (274, 12, None)],
+        )];
+        assert_eq!(
+            csv(&predictions, &[0]),
+            "rank,score,anchor,mass_1,delta_1,intensity_1,mass_2,delta_2,intensity_2\n\
+             0,30,300,286,14,30,274,12,",
+        );
+        let json = json(&predictions, &[0]).unwrap();
+        assert!(json.contains("\"masses\": [\n      286,\n      274\n    ]"));
+        assert!(json.contains("null"));
+    }
+}
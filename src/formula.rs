@@ -0,0 +1,298 @@
+//! Molecular formula: `C19H31NO2`, `C6H5Cl`, `(CH3)3SiCl`, `C5[13C]H12`,
+//! `CD3OD`, `C7H7+` (a trailing charge is `+`, `-`, `++` or `+2`).
+
+use crate::widget::atom::Element;
+use anyhow::{Error, Result};
+use nom::{
+    branch::alt,
+    character::complete::{char, digit1, one_of, satisfy},
+    combinator::{all_consuming, map, map_opt, map_res, opt, recognize, value},
+    multi::{fold_many1, many1},
+    sequence::{delimited, pair},
+    IResult,
+};
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::{self, FromStr},
+};
+
+/// Electron mass
+pub const ELECTRON: f64 = 0.000548579909;
+
+pub fn parse(input: &str) -> Result<Formula, nom::Err<nom::error::Error<&str>>> {
+    let (_, (atoms, charge)) = all_consuming(pair(atoms, opt(charge)))(input)?;
+    Ok(Formula {
+        atoms,
+        charge: charge.unwrap_or_default(),
+    })
+}
+
+fn atoms(input: &str) -> IResult<&str, BTreeMap<Atom, usize>> {
+    fold_many1(
+        pair(
+            alt((
+                map(atom, |atom| BTreeMap::from([(atom, 1)])),
+                delimited(char('('), atoms, char(')')),
+            )),
+            opt(number),
+        ),
+        BTreeMap::new,
+        |mut atoms, (group, count)| {
+            for (atom, number) in group {
+                *atoms.entry(atom).or_default() += number * count.unwrap_or(1);
+            }
+            atoms
+        },
+    )(input)
+}
+
+fn atom(input: &str) -> IResult<&str, Atom> {
+    alt((
+        map_opt(
+            delimited(char('['), pair(number, symbol), char(']')),
+            |(mass_number, symbol)| Atom::isotope(symbol, mass_number),
+        ),
+        map_opt(symbol, Atom::new),
+        map_opt(value(2, char('D')), |mass_number| {
+            Atom::isotope("H", mass_number)
+        }),
+        map_opt(value(3, char('T')), |mass_number| {
+            Atom::isotope("H", mass_number)
+        }),
+    ))(input)
+}
+
+fn symbol(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        satisfy(|c| c.is_ascii_uppercase()),
+        opt(satisfy(|c| c.is_ascii_lowercase())),
+    ))(input)
+}
+
+fn charge(input: &str) -> IResult<&str, i32> {
+    map(
+        pair(many1(one_of("+-")), opt(number::<i32>)),
+        |(signs, number)| {
+            let sign = if signs[0] == '+' { 1 } else { -1 };
+            sign * number.unwrap_or(signs.len() as _)
+        },
+    )(input)
+}
+
+fn number<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// Molecular formula
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Formula {
+    pub atoms: BTreeMap<Atom, usize>,
+    pub charge: i32,
+}
+
+impl Formula {
+    /// Count of the element atoms (labeled included)
+    pub fn count(&self, symbol: &str) -> usize {
+        self.atoms
+            .iter()
+            .filter(|(atom, _)| atom.element.symbol == symbol)
+            .map(|(_, &count)| count)
+            .sum()
+    }
+
+    /// Nominal mass (sum of the integer masses of the most abundant isotopes)
+    pub fn nominal(&self) -> usize {
+        self.atoms
+            .iter()
+            .map(|(atom, &count)| atom.nominal() * count)
+            .sum()
+    }
+
+    /// Monoisotopic mass (of the most abundant isotopes)
+    pub fn monoisotopic(&self) -> f64 {
+        self.atoms
+            .iter()
+            .map(|(atom, &count)| atom.monoisotopic() * count as f64)
+            .sum::<f64>()
+            - self.charge as f64 * ELECTRON
+    }
+
+    /// Average mass (of the standard atomic weights)
+    pub fn average(&self) -> f64 {
+        self.atoms
+            .iter()
+            .map(|(atom, &count)| atom.average() * count as f64)
+            .sum::<f64>()
+            - self.charge as f64 * ELECTRON
+    }
+
+    /// Mismatch of the nominal mass with the `MW` field
+    pub fn mismatch(&self, mw: u64) -> Option<Mismatch> {
+        let nominal = self.nominal();
+        (nominal as u64 != mw).then_some(Mismatch { nominal, mw })
+    }
+}
+
+/// Hill notation: C, H, then the others alphabetically
+impl Display for Formula {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let order = |atom: &Atom| {
+            let carbon = self.count("C") > 0;
+            match atom.element.symbol {
+                "C" if carbon => 0,
+                "H" if carbon => 1,
+                _ => 2,
+            }
+        };
+        let mut atoms = self.atoms.iter().collect::<Vec<_>>();
+        atoms.sort_by_key(|(atom, _)| (order(atom), atom.element.symbol, atom.mass_number));
+        for (atom, &count) in atoms {
+            write!(f, "{atom}")?;
+            if count > 1 {
+                write!(f, "{count}")?;
+            }
+        }
+        match self.charge {
+            0 => Ok(()),
+            1 => f.write_str("+"),
+            -1 => f.write_str("-"),
+            charge if charge > 0 => write!(f, "+{charge}"),
+            charge => write!(f, "-{}", -charge),
+        }
+    }
+}
+
+impl FromStr for Formula {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(parse(value.trim()).map_err(|error| error.to_owned())?)
+    }
+}
+
+/// Atom (of an isotope if labeled)
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Atom {
+    pub element: &'static Element,
+    pub mass_number: Option<usize>,
+}
+
+impl Atom {
+    fn new(symbol: &str) -> Option<Self> {
+        let element = Element::find(symbol)?;
+        element.most_abundant()?;
+        Some(Self {
+            element,
+            mass_number: None,
+        })
+    }
+
+    fn isotope(symbol: &str, mass_number: usize) -> Option<Self> {
+        let element = Element::find(symbol)?;
+        element.isotope(mass_number)?;
+        Some(Self {
+            element,
+            mass_number: Some(mass_number),
+        })
+    }
+
+    fn label(&self) -> Option<f64> {
+        Some(self.element.isotope(self.mass_number?)?.mass)
+    }
+
+    fn nominal(&self) -> usize {
+        self.mass_number
+            .or_else(|| self.element.nominal())
+            .unwrap_or_default()
+    }
+
+    /// Mass of the isotope (of the most abundant one if unlabeled)
+    pub fn monoisotopic(&self) -> f64 {
+        self.label()
+            .or_else(|| self.element.monoisotopic())
+            .unwrap_or_default()
+    }
+
+    fn average(&self) -> f64 {
+        self.label().unwrap_or(self.element.weight)
+    }
+}
+
+impl Display for Atom {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.mass_number {
+            Some(mass_number) => write!(f, "[{mass_number}{}]", self.element),
+            None => write!(f, "{}", self.element),
+        }
+    }
+}
+
+/// Mismatch of the nominal mass with the `MW` field
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Mismatch {
+    pub nominal: usize,
+    pub mw: u64,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nominal mass {} disagrees with the MW field {}",
+            self.nominal, self.mw,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let formula = "C19H31NO2".parse::<Formula>().unwrap();
+        assert_eq!(formula.count("C"), 19);
+        assert_eq!(formula.count("N"), 1);
+        assert_eq!(formula.to_string(), "C19H31NO2");
+        let formula = "(CH3)3SiCl".parse::<Formula>().unwrap();
+        assert_eq!(formula.to_string(), "C3H9ClSi");
+        let formula = "CD3OD".parse::<Formula>().unwrap();
+        assert_eq!(formula.count("H"), 4);
+        assert_eq!(formula.to_string(), "C[2H]4O");
+        let formula = "C5[13C]H12".parse::<Formula>().unwrap();
+        assert_eq!(formula.count("C"), 6);
+        assert_eq!("C7H7+".parse::<Formula>().unwrap().charge, 1);
+        assert_eq!("SO4--".parse::<Formula>().unwrap().charge, -2);
+        assert_eq!("C6H6+2".parse::<Formula>().unwrap().charge, 2);
+        assert!("C6H5Xx".parse::<Formula>().is_err());
+        assert!("C6(H5".parse::<Formula>().is_err());
+    }
+
+    #[test]
+    fn mass() {
+        let formula = "C19H31NO2".parse::<Formula>().unwrap();
+        assert_eq!(formula.nominal(), 305);
+        assert!((formula.monoisotopic() - 305.235479).abs() < 1e-5);
+        assert!((formula.average() - 305.455).abs() < 1e-3);
+        let formula = "C6H5Cl".parse::<Formula>().unwrap();
+        assert_eq!(formula.nominal(), 112);
+        assert_eq!("C5[13C]H12".parse::<Formula>().unwrap().nominal(), 85);
+        assert_eq!("CD3OD".parse::<Formula>().unwrap().nominal(), 36);
+        let cation = "C7H7+".parse::<Formula>().unwrap();
+        assert!((cation.monoisotopic() - 91.054227).abs() < 1e-5);
+    }
+
+    #[test]
+    fn mismatch() {
+        let formula = "C19H31NO2".parse::<Formula>().unwrap();
+        assert_eq!(formula.mismatch(305), None);
+        assert_eq!(
+            formula.mismatch(307),
+            Some(Mismatch {
+                nominal: 305,
+                mw: 307
+            }),
+        );
+    }
+}
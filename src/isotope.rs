@@ -0,0 +1,152 @@
+//! Theoretical isotope pattern of a molecular formula.
+
+use crate::formula::{Formula, ELECTRON};
+use std::collections::BTreeMap;
+
+/// Abundance (relative to the most abundant peak) below which the peaks are
+/// pruned
+const THRESHOLD: f64 = 1e-6;
+
+/// Mass difference in Da below which the fine structure peaks are merged
+const RESOLUTION: f64 = 1e-5;
+
+/// Isotope pattern
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pattern {
+    /// Fine structure: masses and abundances sorted by mass
+    pub peaks: Vec<(f64, f64)>,
+    nominal: usize,
+    monoisotopic: f64,
+}
+
+impl Pattern {
+    pub fn new(formula: &Formula) -> Self {
+        let mut peaks = vec![(-formula.charge as f64 * ELECTRON, 1.0)];
+        for (atom, &count) in &formula.atoms {
+            let isotopes = match atom.mass_number {
+                Some(_) => vec![(atom.monoisotopic(), 1.0)],
+                None => atom
+                    .element
+                    .isotopes
+                    .iter()
+                    .filter(|isotope| isotope.abundance > 0.0)
+                    .map(|isotope| (isotope.mass, isotope.abundance))
+                    .collect(),
+            };
+            for _ in 0..count {
+                peaks = convolve(&peaks, &isotopes);
+            }
+        }
+        Self {
+            peaks,
+            nominal: formula.nominal(),
+            monoisotopic: formula.monoisotopic(),
+        }
+    }
+
+    /// Pattern collapsed to nominal masses (normalized to the most abundant)
+    pub fn nominal(&self) -> BTreeMap<usize, f64> {
+        let mut nominal = BTreeMap::new();
+        for &(mass, abundance) in &self.peaks {
+            let offset = (mass - self.monoisotopic).round() as isize;
+            if let Some(mass) = self.nominal.checked_add_signed(offset) {
+                *nominal.entry(mass).or_default() += abundance;
+            }
+        }
+        let max = nominal.values().copied().fold(0.0, f64::max);
+        if max > 0.0 {
+            nominal.values_mut().for_each(|abundance| *abundance /= max);
+        }
+        nominal
+    }
+
+    /// Fit of the nominal pattern placed with its monoisotopic peak at `mass`
+    /// to the observed peaks
+    pub fn fit(&self, peaks: &BTreeMap<usize, u64>, mass: usize) -> Fit {
+        let shift = mass as isize - self.nominal as isize;
+        let (mut product, mut theoretical, mut observed) = (0.0, 0.0, 0.0);
+        for (nominal, abundance) in self.nominal() {
+            let intensity = nominal
+                .checked_add_signed(shift)
+                .and_then(|mass| peaks.get(&mass))
+                .copied()
+                .unwrap_or_default() as f64;
+            product += abundance * intensity;
+            theoretical += abundance * abundance;
+            observed += intensity * intensity;
+        }
+        if theoretical == 0.0 || observed == 0.0 {
+            return Fit::default();
+        }
+        Fit {
+            scale: product / theoretical,
+            score: product / (theoretical * observed).sqrt(),
+        }
+    }
+}
+
+/// Fit of a pattern to the observed peaks
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fit {
+    /// Least squares factor scaling the normalized pattern to the intensities
+    pub scale: f64,
+    /// Cosine similarity (1 is a perfect fit)
+    pub score: f64,
+}
+
+fn convolve(peaks: &[(f64, f64)], isotopes: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut convolution = Vec::with_capacity(peaks.len() * isotopes.len());
+    for &(mass, abundance) in peaks {
+        for &(isotope, fraction) in isotopes {
+            convolution.push((mass + isotope, abundance * fraction));
+        }
+    }
+    convolution.sort_by(|lhs, rhs| lhs.0.total_cmp(&rhs.0));
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(convolution.len());
+    for (mass, abundance) in convolution {
+        match merged.last_mut() {
+            Some(last) if mass - last.0 < RESOLUTION => {
+                let sum = last.1 + abundance;
+                last.0 = (last.0 * last.1 + mass * abundance) / sum;
+                last.1 = sum;
+            }
+            _ => merged.push((mass, abundance)),
+        }
+    }
+    let max = merged.iter().map(|peak| peak.1).fold(0.0, f64::max);
+    merged.retain(|peak| peak.1 >= max * THRESHOLD);
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn nominal() {
+        let pattern = Pattern::new(&"C19H31NO2".parse().unwrap());
+        let nominal = pattern.nominal();
+        assert_eq!(nominal[&305], 1.0);
+        assert!((nominal[&306] - 0.2134).abs() < 1e-3);
+        let pattern = Pattern::new(&"C6H5Cl".parse().unwrap());
+        let nominal = pattern.nominal();
+        assert!((nominal[&113] - 0.066).abs() < 1e-3);
+        assert!((nominal[&114] - 0.324).abs() < 1e-2);
+        let fine = Pattern::new(&"CH4".parse().unwrap());
+        assert!((fine.peaks[0].0 - 16.0313).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fit() {
+        let pattern = Pattern::new(&"C6H5Cl".parse().unwrap());
+        let peaks = pattern
+            .nominal()
+            .into_iter()
+            .map(|(mass, abundance)| (mass + 1, (abundance * 1000.0).round() as u64))
+            .collect();
+        let fit = pattern.fit(&peaks, 113);
+        assert!((fit.score - 1.0).abs() < 1e-4);
+        assert!((fit.scale - 1000.0).abs() < 1.0);
+        assert!(pattern.fit(&peaks, 200).score == 0.0);
+    }
+}
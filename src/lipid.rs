@@ -0,0 +1,448 @@
+//! Fatty acid shorthand: `18:2(9Z,12Z)`, `9,12-18:2`, `18:1n-9`, `i-15:0`,
+//! `ai-15:0`, `cy-19:0(11)` (`_` is accepted instead of `:`, as in the file
+//! names).
+
+use anyhow::{bail, Error, Result};
+use itertools::Itertools;
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, one_of},
+    combinator::{all_consuming, map, map_res, opt, value},
+    multi::separated_list1,
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display, Formatter},
+    str::{self, FromStr},
+};
+
+/// Mass of the CH3 group lost first from the chain end
+const METHYL: usize = 15;
+/// Mass of a CH2 group
+const METHYLENE: usize = 14;
+
+pub fn parse(input: &str) -> Result<FattyAcid, nom::Err<nom::error::Error<&str>>> {
+    let (_, (modification, prefix, carbons, bonds, suffix)) = all_consuming(tuple((
+        opt(terminated(modification, char('-'))),
+        opt(terminated(positions, char('-'))),
+        number::<usize>,
+        preceded(one_of(":_"), number::<usize>),
+        opt(alt((
+            map(
+                delimited(char('('), positions, char(')')),
+                Suffix::Positions,
+            ),
+            map(preceded(alt((tag("n-"), tag("ω"))), number), Suffix::Omega),
+        ))),
+    )))(input)?;
+    let mut positions = match suffix {
+        Some(Suffix::Positions(positions)) => positions,
+        // Counted from the methyl end, the others methylene-interrupted
+        // toward the carboxyl
+        Some(Suffix::Omega(omega)) => (0..bonds)
+            .rev()
+            .map(|index| Position {
+                carbon: carbons.saturating_sub(omega + 3 * index),
+                geometry: None,
+            })
+            .collect(),
+        None => prefix.unwrap_or_default(),
+    };
+    let modification = modification.map(|modification| match modification {
+        Modification::Cyclo(_) if !positions.is_empty() => {
+            Modification::Cyclo(Some(positions.remove(0).carbon))
+        }
+        modification => modification,
+    });
+    Ok(FattyAcid {
+        carbons,
+        bonds,
+        positions,
+        modification,
+    })
+}
+
+/// Suffix
+enum Suffix {
+    /// `(9Z,12Z)`
+    Positions(Vec<Position>),
+    /// `n-9`
+    Omega(usize),
+}
+
+fn modification(input: &str) -> IResult<&str, Modification> {
+    alt((
+        value(
+            Modification::Anteiso,
+            alt((tag("anteiso"), tag("ai"), tag("a"))),
+        ),
+        value(Modification::Iso, alt((tag("iso"), tag("i")))),
+        value(Modification::Cyclo(None), alt((tag("cyc"), tag("cy")))),
+    ))(input)
+}
+
+fn positions(input: &str) -> IResult<&str, Vec<Position>> {
+    separated_list1(
+        char(','),
+        map(pair(number, opt(geometry)), |(carbon, geometry)| Position {
+            carbon,
+            geometry,
+        }),
+    )(input)
+}
+
+fn geometry(input: &str) -> IResult<&str, Geometry> {
+    alt((
+        value(Geometry::Cis, one_of("Zc")),
+        value(Geometry::Trans, one_of("Et")),
+    ))(input)
+}
+
+fn number<T: FromStr>(input: &str) -> IResult<&str, T> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// Fatty acid
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FattyAcid {
+    pub carbons: usize,
+    pub bonds: usize,
+    /// Double bond positions (empty if unknown)
+    pub positions: Vec<Position>,
+    pub modification: Option<Modification>,
+}
+
+impl FattyAcid {
+    /// Rings plus double bonds
+    pub fn unsaturation(&self) -> usize {
+        self.bonds + matches!(self.modification, Some(Modification::Cyclo(_))) as usize
+    }
+
+    /// Nominal mass of the free acid (CnH(2n-2u)O2), `None` if the
+    /// unsaturation leaves no hydrogen
+    pub fn mass(&self) -> Option<usize> {
+        METHYLENE
+            .checked_mul(self.carbons)?
+            .checked_add(32)?
+            .checked_sub(self.unsaturation().checked_mul(2)?)
+    }
+
+    /// Carbons of the main chain (without a branch or ring bridge carbon)
+    pub fn main_chain(&self) -> usize {
+        match self.modification {
+            Some(_) => self.carbons.saturating_sub(1),
+            None => self.carbons,
+        }
+    }
+
+    /// Mass lost going from the fragment ending at C(j) to the one ending at
+    /// C(j-1)
+    fn delta(&self, carbon: usize) -> usize {
+        let chain = self.main_chain();
+        let branch = match self.modification {
+            Some(Modification::Iso) => chain.checked_sub(1),
+            Some(Modification::Anteiso) => chain.checked_sub(2),
+            // The ring bridge goes with C(k+1), the ring opens like a double bond
+            Some(Modification::Cyclo(Some(position))) => position.checked_add(1),
+            _ => None,
+        };
+        let double_bond = |carbon| {
+            self.positions
+                .iter()
+                .any(|position| position.carbon == carbon)
+                || self.modification == Some(Modification::Cyclo(Some(carbon)))
+        };
+        if branch == Some(carbon) {
+            2 * METHYLENE
+        } else if double_bond(carbon) {
+            METHYLENE - 2
+        } else {
+            METHYLENE
+        }
+    }
+
+    /// Molecular ion and expected ions of the derivative (empty if the mass
+    /// is out of range)
+    pub fn ions(&self, derivative: Derivative) -> Vec<Ion> {
+        let Some(molecular_ion) = self
+            .mass()
+            .and_then(|mass| mass.checked_add(derivative.shift()))
+        else {
+            return Vec::new();
+        };
+        let mut ions = vec![Ion {
+            mass: molecular_ion,
+            label: "M⁺".to_owned(),
+        }];
+        ions.extend(derivative.ions().iter().map(|&mass| Ion {
+            mass,
+            label: mass.to_string(),
+        }));
+        // Chain fragments, the one ending at C(j) for each j down to C2
+        let chain = self.main_chain();
+        let mut mass = molecular_ion;
+        if let (Some(fragment), Some(carbon)) = (mass.checked_sub(METHYL), chain.checked_sub(1)) {
+            mass = fragment;
+            ions.push(Ion {
+                mass,
+                label: format!("C{carbon}"),
+            });
+        }
+        for carbon in (3..chain).rev() {
+            let Some(fragment) = mass.checked_sub(self.delta(carbon)) else {
+                break;
+            };
+            mass = fragment;
+            ions.push(Ion {
+                mass,
+                label: format!("C{}", carbon - 1),
+            });
+        }
+        ions
+    }
+}
+
+impl Display for FattyAcid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.modification {
+            Some(Modification::Iso) => f.write_str("i-")?,
+            Some(Modification::Anteiso) => f.write_str("ai-")?,
+            Some(Modification::Cyclo(_)) => f.write_str("cy-")?,
+            None => {}
+        }
+        write!(f, "{}:{}", self.carbons, self.bonds)?;
+        if let Some(Modification::Cyclo(Some(position))) = self.modification {
+            write!(f, "({position})")?;
+        } else if !self.positions.is_empty() {
+            write!(f, "({})", self.positions.iter().join(","))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for FattyAcid {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let fatty_acid = parse(value.trim()).map_err(|error| error.to_owned())?;
+        if fatty_acid.carbons < 2 {
+            bail!("chain of {} carbons is too short", fatty_acid.carbons);
+        }
+        if fatty_acid.bonds >= fatty_acid.carbons {
+            bail!(
+                "{} double bonds for {} carbons",
+                fatty_acid.bonds,
+                fatty_acid.carbons,
+            );
+        }
+        if !fatty_acid.positions.is_empty() && fatty_acid.positions.len() != fatty_acid.bonds {
+            bail!(
+                "{} double bond positions for {} double bonds",
+                fatty_acid.positions.len(),
+                fatty_acid.bonds,
+            );
+        }
+        if let Some(position) = fatty_acid
+            .positions
+            .iter()
+            .find(|position| !(1..fatty_acid.carbons).contains(&position.carbon))
+        {
+            bail!("double bond position {position} is out of the chain");
+        }
+        if let Some(Modification::Cyclo(Some(position))) = fatty_acid.modification {
+            if !(1..fatty_acid.carbons).contains(&position) {
+                bail!("ring position {position} is out of the chain");
+            }
+        }
+        if fatty_acid.modification.is_some() && fatty_acid.carbons < 4 {
+            bail!("chain is too short for a branch or ring");
+        }
+        Ok(fatty_acid)
+    }
+}
+
+/// Double bond position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub carbon: usize,
+    pub geometry: Option<Geometry>,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.carbon)?;
+        match self.geometry {
+            Some(Geometry::Cis) => f.write_str("Z"),
+            Some(Geometry::Trans) => f.write_str("E"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Double bond geometry
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Geometry {
+    Cis,
+    Trans,
+}
+
+/// Modification
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Modification {
+    /// Methyl branch at the penultimate carbon
+    Iso,
+    /// Methyl branch at the antepenultimate carbon
+    Anteiso,
+    /// Cyclopropane ring (at the position if known)
+    Cyclo(Option<usize>),
+}
+
+/// Derivative
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum Derivative {
+    MethylEster,
+    #[default]
+    Picolinyl,
+    Dmox,
+    Pyrrolidide,
+}
+
+impl Derivative {
+    pub const ALL: [Self; 4] = [
+        Self::MethylEster,
+        Self::Picolinyl,
+        Self::Dmox,
+        Self::Pyrrolidide,
+    ];
+
+    /// Mass added to the free acid
+    pub fn shift(&self) -> usize {
+        match self {
+            // + CH2
+            Self::MethylEster => 14,
+            // + C6H5N
+            Self::Picolinyl => 91,
+            // + C4H7N - O
+            Self::Dmox | Self::Pyrrolidide => 53,
+        }
+    }
+
+    /// Characteristic ions of the head group
+    pub fn ions(&self) -> &'static [usize] {
+        match self {
+            Self::MethylEster => &[74, 87],
+            Self::Picolinyl => &[92, 108, 151, 164],
+            Self::Dmox => &[113, 126],
+            Self::Pyrrolidide => &[113, 126],
+        }
+    }
+}
+
+impl Display for Derivative {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::MethylEster => "Methyl ester",
+            Self::Picolinyl => "Picolinyl ester",
+            Self::Dmox => "DMOX",
+            Self::Pyrrolidide => "Pyrrolidide",
+        })
+    }
+}
+
+/// Expected ion
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ion {
+    pub mass: usize,
+    pub label: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shorthand() {
+        let linoleic = "18:2(9Z,12Z)".parse::<FattyAcid>().unwrap();
+        assert_eq!(linoleic.carbons, 18);
+        assert_eq!(linoleic.bonds, 2);
+        assert_eq!(linoleic.positions[1].carbon, 12);
+        assert_eq!(linoleic.positions[1].geometry, Some(Geometry::Cis));
+        assert_eq!(linoleic.to_string(), "18:2(9Z,12Z)");
+        let prefixed = "9,12-18_2".parse::<FattyAcid>().unwrap();
+        assert_eq!(prefixed.to_string(), "18:2(9,12)");
+        let omega = "18:1n-9".parse::<FattyAcid>().unwrap();
+        assert_eq!(omega.positions[0].carbon, 9);
+        let omega = "18:2n-6".parse::<FattyAcid>().unwrap();
+        assert_eq!(omega.to_string(), "18:2(9,12)");
+        let omega = "20:4ω6".parse::<FattyAcid>().unwrap();
+        assert_eq!(omega.to_string(), "20:4(5,8,11,14)");
+        assert!("10:4n-3".parse::<FattyAcid>().is_err());
+        assert_eq!(
+            "i-15:0".parse::<FattyAcid>().unwrap().modification,
+            Some(Modification::Iso),
+        );
+        assert_eq!(
+            "ai-15:0".parse::<FattyAcid>().unwrap().modification,
+            Some(Modification::Anteiso),
+        );
+        let cyclo = "cy-19:0(11)".parse::<FattyAcid>().unwrap();
+        assert_eq!(cyclo.modification, Some(Modification::Cyclo(Some(11))));
+        assert_eq!(cyclo.to_string(), "cy-19:0(11)");
+        assert!("cy-19:0(0)".parse::<FattyAcid>().is_err());
+        assert!("cy-19:0(30)".parse::<FattyAcid>().is_err());
+        assert!("18:2(9)".parse::<FattyAcid>().is_err());
+        assert!("18:2x".parse::<FattyAcid>().is_err());
+        assert!("0:0".parse::<FattyAcid>().is_err());
+        assert!("1:0".parse::<FattyAcid>().is_err());
+        assert!("1:30".parse::<FattyAcid>().is_err());
+        assert!("18:18".parse::<FattyAcid>().is_err());
+    }
+
+    #[test]
+    fn mass() {
+        let mass = |shorthand: &str, derivative| {
+            shorthand.parse::<FattyAcid>().unwrap().ions(derivative)[0].mass
+        };
+        assert_eq!(mass("18:0", Derivative::MethylEster), 298);
+        assert_eq!(mass("9,12-18:2", Derivative::Picolinyl), 371);
+        assert_eq!(mass("16:1", Derivative::Picolinyl), 345);
+        assert_eq!(mass("18:0", Derivative::Dmox), 337);
+        assert_eq!(mass("cy-19:0", Derivative::MethylEster), 310);
+    }
+
+    #[test]
+    fn ions() {
+        let shortest = FattyAcid {
+            carbons: 0,
+            ..Default::default()
+        };
+        assert_eq!(shortest.ions(Derivative::MethylEster).len(), 3);
+        let unsaturated = FattyAcid {
+            carbons: 1,
+            bonds: 30,
+            ..Default::default()
+        };
+        assert!(unsaturated.ions(Derivative::MethylEster).is_empty());
+        let ions = "16:1(9)"
+            .parse::<FattyAcid>()
+            .unwrap()
+            .ions(Derivative::Picolinyl);
+        let mass = |label: &str| ions.iter().find(|ion| ion.label == label).unwrap().mass;
+        assert_eq!(mass("C15"), 330);
+        assert_eq!(mass("C9"), 246);
+        assert_eq!(mass("C8"), 234);
+        assert_eq!(mass("C2"), 150);
+        let ions = "i-15:0"
+            .parse::<FattyAcid>()
+            .unwrap()
+            .ions(Derivative::Picolinyl);
+        let masses = ions.iter().map(|ion| ion.mass).collect::<Vec<_>>();
+        // M - 15, M - 43: no M - 29
+        assert!(masses.contains(&(ions[0].mass - 15)));
+        assert!(masses.contains(&(ions[0].mass - 43)));
+        assert!(!masses.contains(&(ions[0].mass - 29)));
+    }
+}
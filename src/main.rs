@@ -45,6 +45,9 @@ fn main() {
 }
 
 mod app;
+mod formula;
+mod isotope;
+mod lipid;
 mod parser;
 mod utils;
 mod widget;
@@ -1,7 +1,10 @@
 pub(crate) use self::{
     bound::{BoundExt, RangeBoundsExt},
     display::Trait as Display,
-    egui::{CollapsingStateExt, DroppedFileExt, InnerResponseExt, ResponseExt, UiExt},
+    egui::{
+        CollapsingStateExt, DroppedFileExt, InnerResponseExt, ResponseExt, SelectableValueFromIter,
+        UiExt,
+    },
     float::FloatExt,
     higher_order_functions::with_index,
     stats::Stats,
@@ -1,236 +1,1464 @@
-// Hydrogenium
-pub const H: f64 = 1.00794;
-// Helium
-pub const HE: f64 = 4.002602;
-// Lithium
-pub const LI: f64 = 6.941;
-// Beryllium
-pub const BE: f64 = 9.012182;
-// Borum
-pub const B: f64 = 10.811;
-// Carboneum
-pub const C: f64 = 12.0107;
-// Nitrogenium
-pub const N: f64 = 14.0067;
-// Oxygenium
-pub const O: f64 = 15.9994;
-// Fluorum
-pub const F: f64 = 18.9984032;
-// Neon
-pub const NE: f64 = 20.1797;
-// Natrium
-pub const NA: f64 = 22.98976928;
-// Magnesium
-pub const MG: f64 = 24.3050;
-// Aluminium
-pub const AL: f64 = 26.9815386;
-// Silicium
-pub const SI: f64 = 28.0855;
-// Phosphorus
-pub const P: f64 = 30.973762;
-// Sulfur, Sulphur
-pub const S: f64 = 32.065;
-// Chlorum
-pub const CL: f64 = 35.453;
-// Argon
-pub const AR: f64 = 39.948;
-// Kalium, Calium
-pub const K: f64 = 39.0983;
-// Calcium
-pub const CA: f64 = 40.078;
-// Scandium
-pub const SC: f64 = 44.955912;
-// Titanium
-pub const TI: f64 = 47.867;
-// Vanadium
-pub const V: f64 = 50.9415;
-// Chromium
-pub const CR: f64 = 51.9961;
-// Manganum, Manganesium
-pub const MN: f64 = 54.938045;
-// Ferrum
-pub const FE: f64 = 55.845;
-// Cobaltum
-pub const CO: f64 = 58.933195;
-// Niccolum
-pub const NI: f64 = 58.6934;
-// Cuprum
-pub const CU: f64 = 63.546;
-// Zincum
-pub const ZN: f64 = 65.409;
-// Gallium
-pub const GA: f64 = 69.723;
-// Germanium
-pub const GE: f64 = 72.64;
-// Arsenicum
-pub const AS: f64 = 74.92160;
-// Selenium
-pub const SE: f64 = 78.96;
-// Bromum
-pub const BR: f64 = 79.904;
-// Krypton, Crypton
-pub const KR: f64 = 83.798;
-// Rubidium
-pub const RB: f64 = 85.4678;
-// Strontium
-pub const SR: f64 = 87.62;
-// Yttrium
-pub const Y: f64 = 88.90585;
-// Zirconium
-pub const ZR: f64 = 91.224;
-// Niobium
-pub const NB: f64 = 92.90638;
-// Molybdaenum
-pub const MO: f64 = 95.94;
-// Technetium*
-pub const TC: f64 = 98.9063;
-// Ruthenium
-pub const RU: f64 = 101.07;
-// Rhodium
-pub const RH: f64 = 102.90550;
-// Palladium
-pub const PD: f64 = 106.42;
-// Argentum
-pub const AG: f64 = 107.8682;
-// Cadmium
-pub const CD: f64 = 112.411;
-// Indium
-pub const IN: f64 = 114.818;
-// Stannum
-pub const SN: f64 = 118.710;
-// Stibium
-pub const SB: f64 = 121.760;
-// Tellurium
-pub const TE: f64 = 127.60;
-// Iodium, Jodium
-pub const I: f64 = 126.90447;
-// Xenon
-pub const XE: f64 = 131.293;
-// Caesium
-pub const CS: f64 = 132.9054519;
-// Barium
-pub const BA: f64 = 137.327;
-// Lanthanum
-pub const LA: f64 = 138.90547;
-// Cerium
-pub const CE: f64 = 140.116;
-// Praseodymium
-pub const PR: f64 = 140.90765;
-// Neodymium
-pub const ND: f64 = 144.242;
-// Promethium*
-pub const PM: f64 = 146.9151;
-// Samarium
-pub const SM: f64 = 150.36;
-// Europium
-pub const EU: f64 = 151.964;
-// Gadolinium
-pub const GD: f64 = 157.25;
-// Terbium
-pub const TB: f64 = 158.92535;
-// Dysprosium
-pub const DY: f64 = 162.500;
-// Holmium
-pub const HO: f64 = 164.93032;
-// Erbium
-pub const ER: f64 = 167.259;
-// Thulium
-pub const TM: f64 = 168.93421;
-// Ytterbium
-pub const YB: f64 = 173.04;
-// Lutetium
-pub const LU: f64 = 174.967;
-// Hafnium
-pub const HF: f64 = 178.49;
-// Tantalum
-pub const TA: f64 = 180.9479;
-// Wolframium
-pub const W: f64 = 183.84;
-// Rhenium
-pub const RE: f64 = 186.207;
-// Osmium
-pub const OS: f64 = 190.23;
-// Iridium
-pub const IR: f64 = 192.217;
-// Platinum
-pub const PT: f64 = 195.084;
-// Aurum
-pub const AU: f64 = 196.966569;
-// Hydrargyrum
-pub const HG: f64 = 200.59;
-// Thallium
-pub const TL: f64 = 204.3833;
-// Plumbum
-pub const PB: f64 = 207.2;
-// Bismuthum
-pub const BI: f64 = 208.98040;
-// Polonium*
-pub const PO: f64 = 208.9824;
-// Astatum*
-pub const AT: f64 = 209.9871;
-// Radon*
-pub const RN: f64 = 222.0176;
-// Francium*
-pub const FR: f64 = 223.0197;
-// Radium*
-pub const RA: f64 = 226.0254;
-// Actinium*
-pub const AC: f64 = 227.0278;
-// Thorium
-pub const TH: f64 = 232.03806;
-// Protactinium
-pub const PA: f64 = 231.03588;
-// Uranium
-pub const U: f64 = 238.02891;
-// Neptunium*
-pub const NP: f64 = 237.0482;
-// Plutonium*
-pub const PU: f64 = 244.0642;
-// Americium*
-pub const AM: f64 = 243.0614;
-// Curium*
-pub const CM: f64 = 247.0703;
-// Berkelium*
-pub const BK: f64 = 247.0703;
-// Californium*
-pub const CF: f64 = 251.0796;
-// Einsteinium*
-pub const ES: f64 = 252.0829;
-// Fermium*
-pub const FM: f64 = 257.0951;
-// Mendelevium, Mendeleevium*
-pub const MD: f64 = 258.0986;
-// Nobelium*
-pub const NO: f64 = 259.1009;
-// Lawrencium, Laurentium*
-pub const LR: f64 = 266.0;
-// Rutherfordium*
-pub const RF: f64 = 267.0;
-// Dubnium*
-pub const DB: f64 = 268.0;
-// Seaborgium*
-pub const SG: f64 = 269.0;
-// Bohrium*
-pub const BH: f64 = 270.0;
-// Hassium*
-pub const HS: f64 = 277.0;
-// Meitnerium*
-pub const MT: f64 = 278.0;
-// Darmstadtium*
-pub const DS: f64 = 281.0;
-// Roentgenium*
-pub const RG: f64 = 282.0;
-// Copernicium*
-pub const CN: f64 = 285.0;
-// Nihonium*
-pub const NH: f64 = 286.0;
-// Flerovium*
-pub const FL: f64 = 289.0;
-// Moscovium*
-pub const MC: f64 = 290.0;
-// Livermorium*
-pub const LV: f64 = 293.0;
-// Tennessine*
-pub const TS: f64 = 294.0;
-// Oganesson*
-pub const OG: f64 = 294.0;
+//! Periodic table: elements with their valences, isotope masses and natural
+//! abundances.
+
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
+
+/// Element
+#[derive(Clone, Copy, Debug)]
+pub struct Element {
+    /// Atomic number
+    pub number: u8,
+    pub symbol: &'static str,
+    /// Latin name
+    pub name: &'static str,
+    /// Standard atomic weight
+    pub weight: f64,
+    /// Common valences (empty if not applicable)
+    pub valences: &'static [u8],
+    /// Stable isotopes (empty if no natural abundance is tabulated)
+    pub isotopes: &'static [Isotope],
+}
+
+impl Element {
+    /// Element of the symbol
+    pub fn find(symbol: &str) -> Option<&'static Self> {
+        ELEMENTS.iter().find(|element| element.symbol == symbol)
+    }
+
+    /// Isotope of the mass number
+    pub fn isotope(&self, mass_number: usize) -> Option<&'static Isotope> {
+        self.isotopes
+            .iter()
+            .find(|isotope| isotope.mass_number == mass_number)
+    }
+
+    /// Most abundant isotope
+    pub fn most_abundant(&self) -> Option<&'static Isotope> {
+        self.isotopes
+            .iter()
+            .max_by(|lhs, rhs| lhs.abundance.total_cmp(&rhs.abundance))
+    }
+
+    /// Nominal mass (the mass number of the most abundant isotope)
+    pub fn nominal(&self) -> Option<usize> {
+        Some(self.most_abundant()?.mass_number)
+    }
+
+    /// Monoisotopic mass (the mass of the most abundant isotope)
+    pub fn monoisotopic(&self) -> Option<f64> {
+        Some(self.most_abundant()?.mass)
+    }
+}
+
+impl PartialEq for Element {
+    fn eq(&self, other: &Self) -> bool {
+        self.number == other.number
+    }
+}
+
+impl Eq for Element {}
+
+impl PartialOrd for Element {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Element {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.number.cmp(&other.number)
+    }
+}
+
+impl Hash for Element {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.number.hash(state);
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.symbol)
+    }
+}
+
+/// Isotope
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Isotope {
+    pub mass_number: usize,
+    /// Exact mass
+    pub mass: f64,
+    /// Natural abundance (fraction of the element atoms)
+    pub abundance: f64,
+}
+
+pub static H: &Element = &ELEMENTS[0];
+pub static C: &Element = &ELEMENTS[5];
+pub static N: &Element = &ELEMENTS[6];
+pub static O: &Element = &ELEMENTS[7];
+pub static F: &Element = &ELEMENTS[8];
+pub static SI: &Element = &ELEMENTS[13];
+pub static P: &Element = &ELEMENTS[14];
+pub static S: &Element = &ELEMENTS[15];
+pub static CL: &Element = &ELEMENTS[16];
+pub static BR: &Element = &ELEMENTS[34];
+pub static I: &Element = &ELEMENTS[52];
+
+/// Elements in the order of the atomic number
+pub static ELEMENTS: [Element; 118] = [
+    Element {
+        number: 1,
+        symbol: "H",
+        name: "Hydrogenium",
+        weight: 1.00794,
+        valences: &[1],
+        isotopes: &[
+            Isotope {
+                mass_number: 1,
+                mass: 1.00782503207,
+                abundance: 0.999885,
+            },
+            Isotope {
+                mass_number: 2,
+                mass: 2.0141017778,
+                abundance: 0.000115,
+            },
+            Isotope {
+                mass_number: 3,
+                mass: 3.0160492777,
+                abundance: 0.0,
+            },
+        ],
+    },
+    Element {
+        number: 2,
+        symbol: "He",
+        name: "Helium",
+        weight: 4.002602,
+        valences: &[0],
+        isotopes: &[
+            Isotope {
+                mass_number: 3,
+                mass: 3.0160293191,
+                abundance: 1.34e-06,
+            },
+            Isotope {
+                mass_number: 4,
+                mass: 4.00260325415,
+                abundance: 0.99999866,
+            },
+        ],
+    },
+    Element {
+        number: 3,
+        symbol: "Li",
+        name: "Lithium",
+        weight: 6.941,
+        valences: &[1],
+        isotopes: &[
+            Isotope {
+                mass_number: 6,
+                mass: 6.015122795,
+                abundance: 0.0759,
+            },
+            Isotope {
+                mass_number: 7,
+                mass: 7.01600455,
+                abundance: 0.9241,
+            },
+        ],
+    },
+    Element {
+        number: 4,
+        symbol: "Be",
+        name: "Beryllium",
+        weight: 9.012182,
+        valences: &[2],
+        isotopes: &[Isotope {
+            mass_number: 9,
+            mass: 9.0121822,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 5,
+        symbol: "B",
+        name: "Borum",
+        weight: 10.811,
+        valences: &[3],
+        isotopes: &[
+            Isotope {
+                mass_number: 10,
+                mass: 10.0129370,
+                abundance: 0.199,
+            },
+            Isotope {
+                mass_number: 11,
+                mass: 11.0093054,
+                abundance: 0.801,
+            },
+        ],
+    },
+    Element {
+        number: 6,
+        symbol: "C",
+        name: "Carboneum",
+        weight: 12.0107,
+        valences: &[4],
+        isotopes: &[
+            Isotope {
+                mass_number: 12,
+                mass: 12.0,
+                abundance: 0.9893,
+            },
+            Isotope {
+                mass_number: 13,
+                mass: 13.0033548378,
+                abundance: 0.0107,
+            },
+        ],
+    },
+    Element {
+        number: 7,
+        symbol: "N",
+        name: "Nitrogenium",
+        weight: 14.0067,
+        valences: &[3, 5],
+        isotopes: &[
+            Isotope {
+                mass_number: 14,
+                mass: 14.0030740048,
+                abundance: 0.99636,
+            },
+            Isotope {
+                mass_number: 15,
+                mass: 15.0001088982,
+                abundance: 0.00364,
+            },
+        ],
+    },
+    Element {
+        number: 8,
+        symbol: "O",
+        name: "Oxygenium",
+        weight: 15.9994,
+        valences: &[2],
+        isotopes: &[
+            Isotope {
+                mass_number: 16,
+                mass: 15.99491461956,
+                abundance: 0.99757,
+            },
+            Isotope {
+                mass_number: 17,
+                mass: 16.99913170,
+                abundance: 0.00038,
+            },
+            Isotope {
+                mass_number: 18,
+                mass: 17.9991610,
+                abundance: 0.00205,
+            },
+        ],
+    },
+    Element {
+        number: 9,
+        symbol: "F",
+        name: "Fluorum",
+        weight: 18.9984032,
+        valences: &[1],
+        isotopes: &[Isotope {
+            mass_number: 19,
+            mass: 18.99840322,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 10,
+        symbol: "Ne",
+        name: "Neon",
+        weight: 20.1797,
+        valences: &[0],
+        isotopes: &[
+            Isotope {
+                mass_number: 20,
+                mass: 19.9924401754,
+                abundance: 0.9048,
+            },
+            Isotope {
+                mass_number: 21,
+                mass: 20.99384668,
+                abundance: 0.0027,
+            },
+            Isotope {
+                mass_number: 22,
+                mass: 21.991385114,
+                abundance: 0.0925,
+            },
+        ],
+    },
+    Element {
+        number: 11,
+        symbol: "Na",
+        name: "Natrium",
+        weight: 22.98976928,
+        valences: &[1],
+        isotopes: &[Isotope {
+            mass_number: 23,
+            mass: 22.9897692809,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 12,
+        symbol: "Mg",
+        name: "Magnesium",
+        weight: 24.3050,
+        valences: &[2],
+        isotopes: &[
+            Isotope {
+                mass_number: 24,
+                mass: 23.985041700,
+                abundance: 0.7899,
+            },
+            Isotope {
+                mass_number: 25,
+                mass: 24.98583692,
+                abundance: 0.1,
+            },
+            Isotope {
+                mass_number: 26,
+                mass: 25.982592929,
+                abundance: 0.1101,
+            },
+        ],
+    },
+    Element {
+        number: 13,
+        symbol: "Al",
+        name: "Aluminium",
+        weight: 26.9815386,
+        valences: &[3],
+        isotopes: &[Isotope {
+            mass_number: 27,
+            mass: 26.98153863,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 14,
+        symbol: "Si",
+        name: "Silicium",
+        weight: 28.0855,
+        valences: &[4],
+        isotopes: &[
+            Isotope {
+                mass_number: 28,
+                mass: 27.9769265325,
+                abundance: 0.92223,
+            },
+            Isotope {
+                mass_number: 29,
+                mass: 28.976494700,
+                abundance: 0.04685,
+            },
+            Isotope {
+                mass_number: 30,
+                mass: 29.97377017,
+                abundance: 0.03092,
+            },
+        ],
+    },
+    Element {
+        number: 15,
+        symbol: "P",
+        name: "Phosphorus",
+        weight: 30.973762,
+        valences: &[3, 5],
+        isotopes: &[Isotope {
+            mass_number: 31,
+            mass: 30.97376163,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 16,
+        symbol: "S",
+        name: "Sulfur",
+        weight: 32.065,
+        valences: &[2, 4, 6],
+        isotopes: &[
+            Isotope {
+                mass_number: 32,
+                mass: 31.97207100,
+                abundance: 0.9499,
+            },
+            Isotope {
+                mass_number: 33,
+                mass: 32.97145876,
+                abundance: 0.0075,
+            },
+            Isotope {
+                mass_number: 34,
+                mass: 33.96786690,
+                abundance: 0.0425,
+            },
+            Isotope {
+                mass_number: 36,
+                mass: 35.96708076,
+                abundance: 0.0001,
+            },
+        ],
+    },
+    Element {
+        number: 17,
+        symbol: "Cl",
+        name: "Chlorum",
+        weight: 35.453,
+        valences: &[1, 3, 5, 7],
+        isotopes: &[
+            Isotope {
+                mass_number: 35,
+                mass: 34.96885268,
+                abundance: 0.7576,
+            },
+            Isotope {
+                mass_number: 37,
+                mass: 36.96590259,
+                abundance: 0.2424,
+            },
+        ],
+    },
+    Element {
+        number: 18,
+        symbol: "Ar",
+        name: "Argon",
+        weight: 39.948,
+        valences: &[0],
+        isotopes: &[
+            Isotope {
+                mass_number: 36,
+                mass: 35.967545106,
+                abundance: 0.003365,
+            },
+            Isotope {
+                mass_number: 38,
+                mass: 37.9627324,
+                abundance: 0.000632,
+            },
+            Isotope {
+                mass_number: 40,
+                mass: 39.9623831225,
+                abundance: 0.996003,
+            },
+        ],
+    },
+    Element {
+        number: 19,
+        symbol: "K",
+        name: "Kalium",
+        weight: 39.0983,
+        valences: &[1],
+        isotopes: &[
+            Isotope {
+                mass_number: 39,
+                mass: 38.96370668,
+                abundance: 0.932581,
+            },
+            Isotope {
+                mass_number: 40,
+                mass: 39.96399848,
+                abundance: 0.000117,
+            },
+            Isotope {
+                mass_number: 41,
+                mass: 40.96182576,
+                abundance: 0.067302,
+            },
+        ],
+    },
+    Element {
+        number: 20,
+        symbol: "Ca",
+        name: "Calcium",
+        weight: 40.078,
+        valences: &[2],
+        isotopes: &[
+            Isotope {
+                mass_number: 40,
+                mass: 39.96259098,
+                abundance: 0.96941,
+            },
+            Isotope {
+                mass_number: 42,
+                mass: 41.95861801,
+                abundance: 0.00647,
+            },
+            Isotope {
+                mass_number: 43,
+                mass: 42.9587666,
+                abundance: 0.00135,
+            },
+            Isotope {
+                mass_number: 44,
+                mass: 43.9554818,
+                abundance: 0.02086,
+            },
+            Isotope {
+                mass_number: 46,
+                mass: 45.9536926,
+                abundance: 4e-05,
+            },
+            Isotope {
+                mass_number: 48,
+                mass: 47.952534,
+                abundance: 0.00187,
+            },
+        ],
+    },
+    Element {
+        number: 21,
+        symbol: "Sc",
+        name: "Scandium",
+        weight: 44.955912,
+        valences: &[],
+        isotopes: &[Isotope {
+            mass_number: 45,
+            mass: 44.9559119,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 22,
+        symbol: "Ti",
+        name: "Titanium",
+        weight: 47.867,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 23,
+        symbol: "V",
+        name: "Vanadium",
+        weight: 50.9415,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 24,
+        symbol: "Cr",
+        name: "Chromium",
+        weight: 51.9961,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 25,
+        symbol: "Mn",
+        name: "Manganum",
+        weight: 54.938045,
+        valences: &[],
+        isotopes: &[Isotope {
+            mass_number: 55,
+            mass: 54.9380451,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 26,
+        symbol: "Fe",
+        name: "Ferrum",
+        weight: 55.845,
+        valences: &[],
+        isotopes: &[
+            Isotope {
+                mass_number: 54,
+                mass: 53.9396105,
+                abundance: 0.05845,
+            },
+            Isotope {
+                mass_number: 56,
+                mass: 55.9349375,
+                abundance: 0.91754,
+            },
+            Isotope {
+                mass_number: 57,
+                mass: 56.9353940,
+                abundance: 0.02119,
+            },
+            Isotope {
+                mass_number: 58,
+                mass: 57.9332756,
+                abundance: 0.00282,
+            },
+        ],
+    },
+    Element {
+        number: 27,
+        symbol: "Co",
+        name: "Cobaltum",
+        weight: 58.933195,
+        valences: &[],
+        isotopes: &[Isotope {
+            mass_number: 59,
+            mass: 58.9331950,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 28,
+        symbol: "Ni",
+        name: "Niccolum",
+        weight: 58.6934,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 29,
+        symbol: "Cu",
+        name: "Cuprum",
+        weight: 63.546,
+        valences: &[],
+        isotopes: &[
+            Isotope {
+                mass_number: 63,
+                mass: 62.9295975,
+                abundance: 0.6915,
+            },
+            Isotope {
+                mass_number: 65,
+                mass: 64.9277895,
+                abundance: 0.3085,
+            },
+        ],
+    },
+    Element {
+        number: 30,
+        symbol: "Zn",
+        name: "Zincum",
+        weight: 65.409,
+        valences: &[],
+        isotopes: &[
+            Isotope {
+                mass_number: 64,
+                mass: 63.9291422,
+                abundance: 0.4917,
+            },
+            Isotope {
+                mass_number: 66,
+                mass: 65.9260334,
+                abundance: 0.2773,
+            },
+            Isotope {
+                mass_number: 67,
+                mass: 66.9271273,
+                abundance: 0.0404,
+            },
+            Isotope {
+                mass_number: 68,
+                mass: 67.9248442,
+                abundance: 0.1845,
+            },
+            Isotope {
+                mass_number: 70,
+                mass: 69.9253193,
+                abundance: 0.0061,
+            },
+        ],
+    },
+    Element {
+        number: 31,
+        symbol: "Ga",
+        name: "Gallium",
+        weight: 69.723,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 32,
+        symbol: "Ge",
+        name: "Germanium",
+        weight: 72.64,
+        valences: &[4],
+        isotopes: &[],
+    },
+    Element {
+        number: 33,
+        symbol: "As",
+        name: "Arsenicum",
+        weight: 74.92160,
+        valences: &[3, 5],
+        isotopes: &[Isotope {
+            mass_number: 75,
+            mass: 74.9215965,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 34,
+        symbol: "Se",
+        name: "Selenium",
+        weight: 78.96,
+        valences: &[2, 4, 6],
+        isotopes: &[
+            Isotope {
+                mass_number: 74,
+                mass: 73.9224764,
+                abundance: 0.0089,
+            },
+            Isotope {
+                mass_number: 76,
+                mass: 75.9192136,
+                abundance: 0.0937,
+            },
+            Isotope {
+                mass_number: 77,
+                mass: 76.9199140,
+                abundance: 0.0763,
+            },
+            Isotope {
+                mass_number: 78,
+                mass: 77.9173091,
+                abundance: 0.2377,
+            },
+            Isotope {
+                mass_number: 80,
+                mass: 79.9165213,
+                abundance: 0.4961,
+            },
+            Isotope {
+                mass_number: 82,
+                mass: 81.9166994,
+                abundance: 0.0873,
+            },
+        ],
+    },
+    Element {
+        number: 35,
+        symbol: "Br",
+        name: "Bromum",
+        weight: 79.904,
+        valences: &[1, 3, 5, 7],
+        isotopes: &[
+            Isotope {
+                mass_number: 79,
+                mass: 78.9183371,
+                abundance: 0.5069,
+            },
+            Isotope {
+                mass_number: 81,
+                mass: 80.9162906,
+                abundance: 0.4931,
+            },
+        ],
+    },
+    Element {
+        number: 36,
+        symbol: "Kr",
+        name: "Krypton",
+        weight: 83.798,
+        valences: &[0],
+        isotopes: &[],
+    },
+    Element {
+        number: 37,
+        symbol: "Rb",
+        name: "Rubidium",
+        weight: 85.4678,
+        valences: &[1],
+        isotopes: &[],
+    },
+    Element {
+        number: 38,
+        symbol: "Sr",
+        name: "Strontium",
+        weight: 87.62,
+        valences: &[2],
+        isotopes: &[],
+    },
+    Element {
+        number: 39,
+        symbol: "Y",
+        name: "Yttrium",
+        weight: 88.90585,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 40,
+        symbol: "Zr",
+        name: "Zirconium",
+        weight: 91.224,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 41,
+        symbol: "Nb",
+        name: "Niobium",
+        weight: 92.90638,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 42,
+        symbol: "Mo",
+        name: "Molybdaenum",
+        weight: 95.94,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 43,
+        symbol: "Tc",
+        name: "Technetium",
+        weight: 98.9063,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 44,
+        symbol: "Ru",
+        name: "Ruthenium",
+        weight: 101.07,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 45,
+        symbol: "Rh",
+        name: "Rhodium",
+        weight: 102.90550,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 46,
+        symbol: "Pd",
+        name: "Palladium",
+        weight: 106.42,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 47,
+        symbol: "Ag",
+        name: "Argentum",
+        weight: 107.8682,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 48,
+        symbol: "Cd",
+        name: "Cadmium",
+        weight: 112.411,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 49,
+        symbol: "In",
+        name: "Indium",
+        weight: 114.818,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 50,
+        symbol: "Sn",
+        name: "Stannum",
+        weight: 118.710,
+        valences: &[2, 4],
+        isotopes: &[],
+    },
+    Element {
+        number: 51,
+        symbol: "Sb",
+        name: "Stibium",
+        weight: 121.760,
+        valences: &[3, 5],
+        isotopes: &[],
+    },
+    Element {
+        number: 52,
+        symbol: "Te",
+        name: "Tellurium",
+        weight: 127.60,
+        valences: &[2, 4, 6],
+        isotopes: &[],
+    },
+    Element {
+        number: 53,
+        symbol: "I",
+        name: "Iodium",
+        weight: 126.90447,
+        valences: &[1, 3, 5, 7],
+        isotopes: &[Isotope {
+            mass_number: 127,
+            mass: 126.904473,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 54,
+        symbol: "Xe",
+        name: "Xenon",
+        weight: 131.293,
+        valences: &[0],
+        isotopes: &[],
+    },
+    Element {
+        number: 55,
+        symbol: "Cs",
+        name: "Caesium",
+        weight: 132.9054519,
+        valences: &[1],
+        isotopes: &[Isotope {
+            mass_number: 133,
+            mass: 132.905451933,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 56,
+        symbol: "Ba",
+        name: "Barium",
+        weight: 137.327,
+        valences: &[2],
+        isotopes: &[],
+    },
+    Element {
+        number: 57,
+        symbol: "La",
+        name: "Lanthanum",
+        weight: 138.90547,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 58,
+        symbol: "Ce",
+        name: "Cerium",
+        weight: 140.116,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 59,
+        symbol: "Pr",
+        name: "Praseodymium",
+        weight: 140.90765,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 60,
+        symbol: "Nd",
+        name: "Neodymium",
+        weight: 144.242,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 61,
+        symbol: "Pm",
+        name: "Promethium",
+        weight: 146.9151,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 62,
+        symbol: "Sm",
+        name: "Samarium",
+        weight: 150.36,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 63,
+        symbol: "Eu",
+        name: "Europium",
+        weight: 151.964,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 64,
+        symbol: "Gd",
+        name: "Gadolinium",
+        weight: 157.25,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 65,
+        symbol: "Tb",
+        name: "Terbium",
+        weight: 158.92535,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 66,
+        symbol: "Dy",
+        name: "Dysprosium",
+        weight: 162.500,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 67,
+        symbol: "Ho",
+        name: "Holmium",
+        weight: 164.93032,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 68,
+        symbol: "Er",
+        name: "Erbium",
+        weight: 167.259,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 69,
+        symbol: "Tm",
+        name: "Thulium",
+        weight: 168.93421,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 70,
+        symbol: "Yb",
+        name: "Ytterbium",
+        weight: 173.04,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 71,
+        symbol: "Lu",
+        name: "Lutetium",
+        weight: 174.967,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 72,
+        symbol: "Hf",
+        name: "Hafnium",
+        weight: 178.49,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 73,
+        symbol: "Ta",
+        name: "Tantalum",
+        weight: 180.9479,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 74,
+        symbol: "W",
+        name: "Wolframium",
+        weight: 183.84,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 75,
+        symbol: "Re",
+        name: "Rhenium",
+        weight: 186.207,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 76,
+        symbol: "Os",
+        name: "Osmium",
+        weight: 190.23,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 77,
+        symbol: "Ir",
+        name: "Iridium",
+        weight: 192.217,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 78,
+        symbol: "Pt",
+        name: "Platinum",
+        weight: 195.084,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 79,
+        symbol: "Au",
+        name: "Aurum",
+        weight: 196.966569,
+        valences: &[],
+        isotopes: &[Isotope {
+            mass_number: 197,
+            mass: 196.9665687,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 80,
+        symbol: "Hg",
+        name: "Hydrargyrum",
+        weight: 200.59,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 81,
+        symbol: "Tl",
+        name: "Thallium",
+        weight: 204.3833,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 82,
+        symbol: "Pb",
+        name: "Plumbum",
+        weight: 207.2,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 83,
+        symbol: "Bi",
+        name: "Bismuthum",
+        weight: 208.98040,
+        valences: &[],
+        isotopes: &[Isotope {
+            mass_number: 209,
+            mass: 208.9803987,
+            abundance: 1.0,
+        }],
+    },
+    Element {
+        number: 84,
+        symbol: "Po",
+        name: "Polonium",
+        weight: 208.9824,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 85,
+        symbol: "At",
+        name: "Astatum",
+        weight: 209.9871,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 86,
+        symbol: "Rn",
+        name: "Radon",
+        weight: 222.0176,
+        valences: &[0],
+        isotopes: &[],
+    },
+    Element {
+        number: 87,
+        symbol: "Fr",
+        name: "Francium",
+        weight: 223.0197,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 88,
+        symbol: "Ra",
+        name: "Radium",
+        weight: 226.0254,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 89,
+        symbol: "Ac",
+        name: "Actinium",
+        weight: 227.0278,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 90,
+        symbol: "Th",
+        name: "Thorium",
+        weight: 232.03806,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 91,
+        symbol: "Pa",
+        name: "Protactinium",
+        weight: 231.03588,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 92,
+        symbol: "U",
+        name: "Uranium",
+        weight: 238.02891,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 93,
+        symbol: "Np",
+        name: "Neptunium",
+        weight: 237.0482,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 94,
+        symbol: "Pu",
+        name: "Plutonium",
+        weight: 244.0642,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 95,
+        symbol: "Am",
+        name: "Americium",
+        weight: 243.0614,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 96,
+        symbol: "Cm",
+        name: "Curium",
+        weight: 247.0703,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 97,
+        symbol: "Bk",
+        name: "Berkelium",
+        weight: 247.0703,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 98,
+        symbol: "Cf",
+        name: "Californium",
+        weight: 251.0796,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 99,
+        symbol: "Es",
+        name: "Einsteinium",
+        weight: 252.0829,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 100,
+        symbol: "Fm",
+        name: "Fermium",
+        weight: 257.0951,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 101,
+        symbol: "Md",
+        name: "Mendelevium",
+        weight: 258.0986,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 102,
+        symbol: "No",
+        name: "Nobelium",
+        weight: 259.1009,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 103,
+        symbol: "Lr",
+        name: "Lawrencium",
+        weight: 266.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 104,
+        symbol: "Rf",
+        name: "Rutherfordium",
+        weight: 267.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 105,
+        symbol: "Db",
+        name: "Dubnium",
+        weight: 268.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 106,
+        symbol: "Sg",
+        name: "Seaborgium",
+        weight: 269.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 107,
+        symbol: "Bh",
+        name: "Bohrium",
+        weight: 270.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 108,
+        symbol: "Hs",
+        name: "Hassium",
+        weight: 277.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 109,
+        symbol: "Mt",
+        name: "Meitnerium",
+        weight: 278.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 110,
+        symbol: "Ds",
+        name: "Darmstadtium",
+        weight: 281.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 111,
+        symbol: "Rg",
+        name: "Roentgenium",
+        weight: 282.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 112,
+        symbol: "Cn",
+        name: "Copernicium",
+        weight: 285.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 113,
+        symbol: "Nh",
+        name: "Nihonium",
+        weight: 286.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 114,
+        symbol: "Fl",
+        name: "Flerovium",
+        weight: 289.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 115,
+        symbol: "Mc",
+        name: "Moscovium",
+        weight: 290.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 116,
+        symbol: "Lv",
+        name: "Livermorium",
+        weight: 293.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 117,
+        symbol: "Ts",
+        name: "Tennessine",
+        weight: 294.0,
+        valences: &[],
+        isotopes: &[],
+    },
+    Element {
+        number: 118,
+        symbol: "Og",
+        name: "Oganesson",
+        weight: 294.0,
+        valences: &[],
+        isotopes: &[],
+    },
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn elements() {
+        for (index, element) in ELEMENTS.iter().enumerate() {
+            assert_eq!(element.number as usize, index + 1);
+            if !element.isotopes.is_empty() {
+                let abundance = element
+                    .isotopes
+                    .iter()
+                    .map(|isotope| isotope.abundance)
+                    .sum::<f64>();
+                assert!((abundance - 1.0).abs() < 1e-3, "{element}: {abundance}");
+            }
+        }
+        assert_eq!(Element::find("C"), Some(C));
+        assert_eq!(Element::find("Cl").unwrap().nominal(), Some(35));
+        assert_eq!(Element::find("Br").unwrap().valences, [1, 3, 5, 7]);
+        assert_eq!(C.isotope(13).map(|isotope| isotope.abundance), Some(0.0107));
+        assert_eq!(Element::find("Xx"), None);
+    }
+}
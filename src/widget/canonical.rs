@@ -0,0 +1,200 @@
+//! Canonical labelling: Morgan-like refinement of the atom classes by their
+//! extended connectivity, the remaining ties broken one atom at a time (each
+//! followed by a new refinement), so that isomorphic molecules get the same
+//! ranks, the same SMILES and the same key.
+
+use super::{atom::H, molecule::Molecule};
+use petgraph::{graph::EdgeIndex, visit::EdgeRef};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashSet},
+    fmt::{self, Display, Formatter},
+};
+
+/// Canonical key (the canonical SMILES, the aromatic bonds written `:`)
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Key(String);
+
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Molecule {
+    /// Canonical ranks of the atoms, terminal atoms first
+    pub fn canonical_ranks(&self) -> Vec<usize> {
+        self.ranks(&self.aromatic_bonds())
+    }
+
+    /// Canonical key (equal for the isomorphic molecules, whatever the
+    /// Kekulé structure of their aromatic rings)
+    pub fn key(&self) -> Key {
+        self.canonical().0
+    }
+
+    /// Canonical key and ranks of the atoms
+    pub fn canonical(&self) -> (Key, Vec<usize>) {
+        let aromatic = self.aromatic_bonds();
+        let ranks = self.ranks(&aromatic);
+        (Key(self.write(&ranks, &aromatic)), ranks)
+    }
+
+    /// Canonical ranks with the aromatic bonds in their own class
+    fn ranks(&self, aromatic: &HashSet<EdgeIndex>) -> Vec<usize> {
+        let invariants = self
+            .node_indices()
+            .map(|index| {
+                let hydrogens = self
+                    .neighbors(index)
+                    .filter(|&neighbor| self[neighbor] == H)
+                    .count();
+                let aromatic = self
+                    .edges(index)
+                    .filter(|edge| aromatic.contains(&edge.id()))
+                    .count();
+                (
+                    self.heavy(index).count(),
+                    self[index].number,
+                    hydrogens,
+                    self.bonds(index),
+                    aromatic,
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut ranks = self.refine(dense(&invariants), aromatic);
+        // Breaks the ties of the lowest tied class
+        while let Some(tied) = lowest_tie(&ranks) {
+            let classes = ranks
+                .iter()
+                .enumerate()
+                .map(|(index, &rank)| (rank, index != tied))
+                .collect::<Vec<_>>();
+            ranks = self.refine(dense(&classes), aromatic);
+        }
+        ranks
+    }
+
+    /// Ranks refined by the ranks of the neighbors (and the bond classes, the
+    /// aromatic and multiple bonds first) until they are stable
+    fn refine(&self, mut ranks: Vec<usize>, aromatic: &HashSet<EdgeIndex>) -> Vec<usize> {
+        loop {
+            let classes = self
+                .node_indices()
+                .map(|index| {
+                    let mut neighbors = self
+                        .edges(index)
+                        .map(|edge| {
+                            let neighbor = match edge.source() == index {
+                                true => edge.target(),
+                                false => edge.source(),
+                            };
+                            let class = match aromatic.contains(&edge.id()) {
+                                true => Bond::Aromatic,
+                                false => Bond::Order(*edge.weight()),
+                            };
+                            (ranks[neighbor.index()], Reverse(class))
+                        })
+                        .collect::<Vec<_>>();
+                    neighbors.sort();
+                    (ranks[index.index()], neighbors)
+                })
+                .collect::<Vec<_>>();
+            let refined = dense(&classes);
+            if count(&refined) <= count(&ranks) {
+                return ranks;
+            }
+            ranks = refined;
+        }
+    }
+}
+
+/// Bond class (an aromatic bond whatever its Kekulé order)
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Bond {
+    Order(u8),
+    Aromatic,
+}
+
+/// Molecules without the isomorphic duplicates (the first ones kept)
+pub fn dedup(molecules: impl IntoIterator<Item = Molecule>) -> Vec<Molecule> {
+    let mut keys = HashSet::new();
+    molecules
+        .into_iter()
+        .filter(|molecule| keys.insert(molecule.key()))
+        .collect()
+}
+
+/// Atom of the lowest rank shared by several atoms
+fn lowest_tie(ranks: &[usize]) -> Option<usize> {
+    let mut seen = HashSet::new();
+    let tied = ranks.iter().filter(|&&rank| !seen.insert(rank)).min()?;
+    ranks.iter().position(|rank| rank == tied)
+}
+
+/// Dense ranks of the classes
+fn dense<T: Ord>(classes: &[T]) -> Vec<usize> {
+    let sorted = classes.iter().collect::<BTreeSet<_>>();
+    classes
+        .iter()
+        .map(|class| {
+            sorted
+                .iter()
+                .position(|&other| other == class)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn count(ranks: &[usize]) -> usize {
+    ranks.iter().collect::<BTreeSet<_>>().len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn keys() {
+        let key = |smiles: &str| smiles.parse::<Molecule>().unwrap().key();
+        assert_eq!(key("OCC(C)CC=C"), key("C=CCC(C)CO"));
+        assert_eq!(key("C1=CC=CC=C1C"), key("Cc1ccccc1"));
+        assert_eq!(key("CC(C)(C)c1ccc(O)cc1"), key("Oc1ccc(cc1)C(C)(C)C"));
+        assert_ne!(key("CC=CC"), key("C=CCC"));
+        // The Kekulé structures of o-xylene
+        assert_eq!(key("Cc1ccccc1C"), key("CC1=C(C)C=CC=C1"));
+        assert_eq!(key("CC1=CC=CC=C1C"), key("CC1=C(C)C=CC=C1"));
+        assert_ne!(key("Cc1ccccc1C"), key("Cc1cccc(C)c1"));
+        // Relabelled atoms
+        let molecule = "CC(=O)OCC1CCCC1".parse::<Molecule>().unwrap();
+        let mut relabelled = Molecule::new();
+        let atoms = molecule
+            .node_indices()
+            .rev()
+            .map(|index| (index, relabelled.add_node(molecule[index])))
+            .collect::<HashMap<_, _>>();
+        for edge in molecule.edge_indices().rev() {
+            let (lhs, rhs) = molecule.edge_endpoints(edge).unwrap();
+            relabelled.add_edge(atoms[&rhs], atoms[&lhs], molecule[edge]);
+        }
+        assert_eq!(molecule.key(), relabelled.key());
+        let ranks = relabelled.canonical_ranks();
+        assert_eq!(count(&ranks), ranks.len());
+    }
+
+    #[test]
+    fn duplicates() {
+        let molecules = ["C=CCCCC", "CCCCC=C", "CC=CCCC", "CCC=CCC", "CCCC=CC"]
+            .map(|smiles| smiles.parse::<Molecule>().unwrap());
+        let unique = dedup(molecules);
+        assert_eq!(unique.len(), 3);
+        let lookup = unique
+            .iter()
+            .enumerate()
+            .map(|(index, molecule)| (molecule.key(), index))
+            .collect::<HashMap<_, _>>();
+        let hexene = "C(C)C=CCC".parse::<Molecule>().unwrap();
+        assert_eq!(lookup.get(&hexene.key()), Some(&2));
+    }
+}
@@ -0,0 +1,620 @@
+//! 2D depiction: coordinates of the atoms and a skeletal formula.
+//!
+//! The rings are regular polygons, fused to the placed rings by their shared
+//! bond or spiro-attached by their shared atom; the chains zig-zag and the
+//! branches are spread in the free angle around their atom. The components
+//! are laid out side by side. The bond length is one.
+//!
+//! The skeletal formula hides the hydrogens and the labels of the bonded
+//! carbons; the other atoms are labelled with their hydrogens and charges.
+
+use super::{
+    atom::{Element, C, H},
+    canonical::Key,
+    molecule::Molecule,
+};
+use egui::{
+    emath::Rot2,
+    util::cache::{ComputerMut, FrameCache},
+    vec2, Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, TextStyle, Ui, Vec2, Widget,
+};
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+use std::{
+    collections::{HashSet, VecDeque},
+    f32::consts::{PI, TAU},
+    hash::{Hash, Hasher},
+};
+
+/// Gap between the components (in bond lengths)
+const GAP: f32 = 1.5;
+
+/// Canonicalized
+type Canonicalized = FrameCache<(Key, Vec<usize>), Canonicalizer>;
+
+/// Canonicalizer (keyed by the atoms and bonds of the molecule)
+#[derive(Default)]
+struct Canonicalizer;
+
+impl ComputerMut<&Molecule, (Key, Vec<usize>)> for Canonicalizer {
+    fn compute(&mut self, molecule: &Molecule) -> (Key, Vec<usize>) {
+        molecule.canonical()
+    }
+}
+
+/// Laid out
+type LaidOut = FrameCache<Depiction, Layouter>;
+
+/// Layouter key (hashed by the canonical key only)
+#[derive(Clone, Copy)]
+struct LayouterKey<'a> {
+    key: &'a Key,
+    molecule: &'a Molecule,
+    ranks: &'a [usize],
+}
+
+impl Hash for LayouterKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+/// Layouter
+#[derive(Default)]
+struct Layouter;
+
+impl ComputerMut<LayouterKey<'_>, Depiction> for Layouter {
+    fn compute(&mut self, args: LayouterKey) -> Depiction {
+        let mut positions = vec![Pos2::ZERO; args.ranks.len()];
+        for (atom, position) in args.molecule.layout().into_iter().enumerate() {
+            positions[args.ranks[atom]] = position;
+        }
+        let rings = args
+            .molecule
+            .rings()
+            .into_iter()
+            .map(|ring| ring.iter().map(|atom| args.ranks[atom.index()]).collect())
+            .collect();
+        Depiction { positions, rings }
+    }
+}
+
+/// Positions and rings by the canonical ranks of the atoms (shared by the
+/// isomorphic molecules)
+#[derive(Clone, Debug, Default)]
+struct Depiction {
+    positions: Vec<Pos2>,
+    rings: Vec<Vec<usize>>,
+}
+
+/// Depiction widget of the size
+pub fn depiction(molecule: &Molecule, size: Vec2) -> impl Widget + '_ {
+    move |ui: &mut Ui| {
+        let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+        if ui.is_rect_visible(rect) {
+            paint(ui, rect, molecule);
+        }
+        response
+    }
+}
+
+impl Molecule {
+    /// 2D coordinates of the atoms (the hydrogens at their heavy atom)
+    pub fn layout(&self) -> Vec<Pos2> {
+        let mut layout = Layout {
+            molecule: self,
+            positions: vec![None; self.node_count()],
+            turns: vec![1.0; self.node_count()],
+            rings: self.rings(),
+        };
+        let mut right = 0.0;
+        for atom in self.node_indices().filter(|&atom| self.is_shown(atom)) {
+            if layout.positions[atom.index()].is_some() {
+                continue;
+            }
+            let component = layout.component(atom);
+            let Some(rect) = component
+                .iter()
+                .filter_map(|atom| layout.positions[atom.index()])
+                .map(|position| Rect::from_center_size(position, Vec2::ZERO))
+                .reduce(Rect::union)
+            else {
+                continue;
+            };
+            let offset = vec2(right - rect.left(), -rect.center().y);
+            for atom in component {
+                if let Some(position) = &mut layout.positions[atom.index()] {
+                    *position += offset;
+                }
+            }
+            right += rect.width() + GAP;
+        }
+        // The hydrogens at their heavy atom
+        self.node_indices()
+            .map(|atom| {
+                layout.positions[atom.index()]
+                    .or_else(|| {
+                        let neighbor = self.neighbors(atom).next()?;
+                        layout.positions[neighbor.index()]
+                    })
+                    .unwrap_or(Pos2::ZERO)
+            })
+            .collect()
+    }
+
+    /// The atom is drawn (not a hydrogen of a heavy atom)
+    fn is_shown(&self, atom: NodeIndex) -> bool {
+        self[atom] != H || self.neighbors(atom).all(|neighbor| self[neighbor] == H)
+    }
+
+    /// Hydrogens of the atom (explicit and implicit)
+    fn hydrogens(&self, atom: NodeIndex) -> usize {
+        let explicit = self
+            .neighbors(atom)
+            .filter(|&neighbor| self[neighbor] == H && !self.is_shown(neighbor))
+            .count();
+        explicit + self.implicit_hydrogens(atom)
+    }
+}
+
+/// Layout of the atoms
+struct Layout<'a> {
+    molecule: &'a Molecule,
+    positions: Vec<Option<Pos2>>,
+    /// Turns of the zig-zag (the sign of the angle to the next chain atom)
+    turns: Vec<f32>,
+    rings: Vec<Vec<NodeIndex>>,
+}
+
+impl Layout<'_> {
+    /// Places the component of the atom (returns its atoms)
+    fn component(&mut self, start: NodeIndex) -> Vec<NodeIndex> {
+        let mut atoms = Vec::new();
+        let mut queue = VecDeque::new();
+        self.positions[start.index()] = Some(Pos2::ZERO);
+        match self.ring_of(start) {
+            Some(ring) => {
+                let ring = self.rings[ring].clone();
+                self.polygon(&ring, start, Vec2::X);
+                queue.extend(ring);
+            }
+            None => queue.push_back(start),
+        }
+        let mut visited = HashSet::new();
+        while let Some(atom) = queue.pop_front() {
+            if !visited.insert(atom) {
+                continue;
+            }
+            atoms.push(atom);
+            queue.extend(self.rings_through(atom));
+            queue.extend(self.neighbors(atom));
+        }
+        atoms
+    }
+
+    /// Places the unplaced rings through the placed atom (returns their atoms)
+    fn rings_through(&mut self, atom: NodeIndex) -> Vec<NodeIndex> {
+        let mut placed = Vec::new();
+        for index in 0..self.rings.len() {
+            let ring = self.rings[index].clone();
+            if !ring.contains(&atom) || ring.iter().all(|atom| self.is_placed(*atom)) {
+                continue;
+            }
+            let length = ring.len();
+            // A placed bond (fused) or the atom (spiro or substituent)
+            let fused = (0..length).find(|&index| {
+                self.is_placed(ring[index]) && self.is_placed(ring[(index + 1) % length])
+            });
+            match fused {
+                Some(index) => self.fuse(&ring, index),
+                None => {
+                    let away = -self
+                        .placed_neighbors(atom)
+                        .fold(Vec2::ZERO, |sum, vector| sum + vector);
+                    let direction = match away.length() < 1e-3 {
+                        true => Vec2::X,
+                        false => away.normalized(),
+                    };
+                    self.polygon(&ring, atom, direction);
+                }
+            }
+            placed.extend(ring);
+        }
+        placed
+    }
+
+    /// Places the unplaced neighbors of the placed atom (returns them)
+    fn neighbors(&mut self, atom: NodeIndex) -> Vec<NodeIndex> {
+        let molecule = self.molecule;
+        let Some(position) = self.positions[atom.index()] else {
+            return Vec::new();
+        };
+        let unplaced = molecule
+            .neighbors(atom)
+            .filter(|&neighbor| molecule.is_shown(neighbor) && !self.is_placed(neighbor))
+            .collect::<Vec<_>>();
+        if unplaced.is_empty() {
+            return unplaced;
+        }
+        let placed = self.placed_neighbors(atom).collect::<Vec<_>>();
+        let count = unplaced.len() as f32;
+        let directions = match &*placed {
+            // Root of a chain
+            [] if unplaced.len() == 1 => vec![Rot2::from_angle(-PI / 6.0) * Vec2::X],
+            // Root of the branches (spread as if from below)
+            [] => (1..=unplaced.len())
+                .map(|index| Rot2::from_angle(index as f32 * TAU / (count + 1.0)) * Vec2::Y)
+                .collect(),
+            // Linear (triple bond or cumulated double bonds)
+            &[parent] if unplaced.len() == 1 && molecule.is_linear(atom) => vec![-parent],
+            // Zig-zag
+            &[parent] if unplaced.len() == 1 => {
+                let turn = self.turns[atom.index()];
+                self.turns[unplaced[0].index()] = -turn;
+                vec![Rot2::from_angle(turn * PI / 3.0) * -parent]
+            }
+            // Branches spread around
+            &[parent] => (1..=unplaced.len())
+                .map(|index| Rot2::from_angle(index as f32 * TAU / (count + 1.0)) * parent)
+                .collect(),
+            placed => {
+                let sum = placed.iter().fold(Vec2::ZERO, |sum, &vector| sum + vector);
+                let away = match sum.length() < 1e-3 {
+                    true => placed[0].rot90(),
+                    false => -sum.normalized(),
+                };
+                let step = (2.0 * PI / 3.0 / count).min(PI / 3.0);
+                (0..unplaced.len())
+                    .map(|index| {
+                        Rot2::from_angle((index as f32 - (count - 1.0) / 2.0) * step) * away
+                    })
+                    .collect()
+            }
+        };
+        for (&neighbor, direction) in unplaced.iter().zip(directions) {
+            self.positions[neighbor.index()] = Some(position + direction);
+        }
+        unplaced
+    }
+
+    /// Places the ring as a regular polygon through the atom, its center in
+    /// the direction
+    fn polygon(&mut self, ring: &[NodeIndex], atom: NodeIndex, direction: Vec2) {
+        let Some(position) = self.positions[atom.index()] else {
+            return;
+        };
+        let length = ring.len();
+        let radius = 0.5 / (PI / length as f32).sin();
+        let center = position + direction * radius;
+        let start = ring
+            .iter()
+            .position(|&other| other == atom)
+            .unwrap_or_default();
+        let angle = (position - center).angle();
+        for step in 1..length {
+            let index = ring[(start + step) % length];
+            if !self.is_placed(index) {
+                let angle = angle + step as f32 * TAU / length as f32;
+                self.positions[index.index()] = Some(center + radius * Vec2::angled(angle));
+            }
+        }
+    }
+
+    /// Places the ring as a regular polygon on the placed bond from the
+    /// atom of the index, away from the placed neighbors
+    fn fuse(&mut self, ring: &[NodeIndex], index: usize) {
+        let length = ring.len();
+        let (lhs, rhs) = (ring[index], ring[(index + 1) % length]);
+        let (Some(from), Some(to)) = (self.positions[lhs.index()], self.positions[rhs.index()])
+        else {
+            return;
+        };
+        let middle = from + (to - from) / 2.0;
+        let mut normal = (to - from).normalized().rot90();
+        let others = self
+            .molecule
+            .neighbors(lhs)
+            .chain(self.molecule.neighbors(rhs))
+            .filter(|&other| other != lhs && other != rhs)
+            .filter_map(|other| self.positions[other.index()])
+            .fold(Vec2::ZERO, |sum, other| sum + (other - middle));
+        if others.dot(normal) > 0.0 {
+            normal = -normal;
+        }
+        let apothem = 0.5 / (PI / length as f32).tan();
+        let center = middle + normal * apothem;
+        let radius = 0.5 / (PI / length as f32).sin();
+        let angle = (from - center).angle();
+        // Turning from the atom to the next one
+        let sign = match Vec2::angled(angle + TAU / length as f32).dot((to - center).normalized())
+            > 0.99
+        {
+            true => 1.0,
+            false => -1.0,
+        };
+        for step in 2..length {
+            let atom = ring[(index + step) % length];
+            if !self.is_placed(atom) {
+                let angle = angle + sign * step as f32 * TAU / length as f32;
+                self.positions[atom.index()] = Some(center + radius * Vec2::angled(angle));
+            }
+        }
+    }
+
+    /// Smallest ring through the atom
+    fn ring_of(&self, atom: NodeIndex) -> Option<usize> {
+        (0..self.rings.len())
+            .filter(|&index| self.rings[index].contains(&atom))
+            .min_by_key(|&index| self.rings[index].len())
+    }
+
+    /// Unit vectors to the placed neighbors of the atom
+    fn placed_neighbors(&self, atom: NodeIndex) -> impl Iterator<Item = Vec2> + '_ {
+        let position = self.positions[atom.index()].unwrap_or(Pos2::ZERO);
+        self.molecule
+            .neighbors(atom)
+            .filter(|&neighbor| self.molecule.is_shown(neighbor))
+            .filter_map(move |neighbor| {
+                Some((self.positions[neighbor.index()]? - position).normalized())
+            })
+    }
+
+    fn is_placed(&self, atom: NodeIndex) -> bool {
+        self.positions[atom.index()].is_some()
+    }
+}
+
+impl Molecule {
+    /// The bonds of the atom are collinear (a triple bond or two double
+    /// bonds)
+    fn is_linear(&self, atom: NodeIndex) -> bool {
+        let orders = self
+            .edges(atom)
+            .map(|edge| *edge.weight())
+            .collect::<Vec<_>>();
+        orders.contains(&3) || orders.iter().filter(|&&order| order == 2).count() == 2
+    }
+}
+
+/// Paints the skeletal formula of the molecule fitted in the rectangle
+pub fn paint(ui: &Ui, rect: Rect, molecule: &Molecule) {
+    let visuals = ui.visuals();
+    let painter = ui.painter_at(rect);
+    let (ranks, depiction) = ui.ctx().memory_mut(|memory| {
+        let (key, ranks) = memory.caches.cache::<Canonicalized>().get(molecule);
+        let depiction = memory.caches.cache::<LaidOut>().get(LayouterKey {
+            key: &key,
+            molecule,
+            ranks: &ranks,
+        });
+        (ranks, depiction)
+    });
+    let mut atoms = vec![NodeIndex::end(); ranks.len()];
+    for (index, &rank) in ranks.iter().enumerate() {
+        atoms[rank] = NodeIndex::new(index);
+    }
+    let positions = ranks
+        .iter()
+        .map(|&rank| depiction.positions[rank])
+        .collect::<Vec<_>>();
+    let rings = depiction
+        .rings
+        .iter()
+        .map(|ring| ring.iter().map(|&rank| atoms[rank]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let shown = molecule
+        .node_indices()
+        .filter(|&atom| molecule.is_shown(atom))
+        .collect::<Vec<_>>();
+    let Some(bounds) = shown
+        .iter()
+        .map(|atom| Rect::from_center_size(positions[atom.index()], Vec2::ZERO))
+        .reduce(Rect::union)
+    else {
+        return;
+    };
+    let size = ui.text_style_height(&TextStyle::Body);
+    // Margin of a label, at most two labels per bond
+    let margin = size;
+    let scale = ((rect.width() - 2.0 * margin) / bounds.width().max(1e-3))
+        .min((rect.height() - 2.0 * margin) / bounds.height().max(1e-3))
+        .min(2.5 * size);
+    let screen =
+        |atom: NodeIndex| rect.center() + (positions[atom.index()] - bounds.center()) * scale;
+    let labeled = |atom: NodeIndex| {
+        molecule[atom] != C
+            || molecule
+                .neighbors(atom)
+                .all(|neighbor| !molecule.is_shown(neighbor))
+    };
+    let font = FontId::proportional(0.8 * size);
+    let stroke = Stroke::new(1.0_f32, visuals.text_color());
+    for edge in molecule.edge_references() {
+        let (source, target) = (edge.source(), edge.target());
+        if !molecule.is_shown(source) || !molecule.is_shown(target) {
+            continue;
+        }
+        let (mut from, mut to) = (screen(source), screen(target));
+        let direction = (to - from).normalized();
+        if labeled(source) {
+            from += direction * 0.4 * size;
+        }
+        if labeled(target) {
+            to -= direction * 0.4 * size;
+        }
+        let offset = direction.rot90() * 0.15 * scale;
+        // Inner line of a ring double bond, toward the center of the ring
+        let ring = rings
+            .iter()
+            .filter(|ring| ring.contains(&source) && ring.contains(&target))
+            .min_by_key(|ring| ring.len());
+        match (*edge.weight(), ring) {
+            (2, Some(ring)) => {
+                let center = ring
+                    .iter()
+                    .fold(Vec2::ZERO, |sum, &atom| sum + screen(atom).to_vec2())
+                    / ring.len() as f32;
+                let inner = match (center.to_pos2() - from).dot(offset) > 0.0 {
+                    true => offset * 1.5,
+                    false => -offset * 1.5,
+                };
+                let shorten = (to - from) * 0.15;
+                painter.line_segment([from, to], stroke);
+                painter.line_segment([from + shorten + inner, to - shorten + inner], stroke);
+            }
+            (2, None) => {
+                painter.line_segment([from + offset, to + offset], stroke);
+                painter.line_segment([from - offset, to - offset], stroke);
+            }
+            (3, _) => {
+                painter.line_segment([from, to], stroke);
+                painter.line_segment([from + offset * 1.5, to + offset * 1.5], stroke);
+                painter.line_segment([from - offset * 1.5, to - offset * 1.5], stroke);
+            }
+            _ => painter.line_segment([from, to], stroke),
+        }
+    }
+    for atom in shown.into_iter().filter(|&atom| labeled(atom)) {
+        let element = molecule[atom];
+        let hydrogens = match element == H {
+            true => 0,
+            false => molecule.hydrogens(atom),
+        };
+        let bonds = molecule.bonds(atom) + molecule.implicit_hydrogens(atom);
+        let mut label = element.symbol.to_owned();
+        match hydrogens {
+            0 => {}
+            1 => label.push('H'),
+            count => label.push_str(&format!("H{}", subscript(count))),
+        }
+        match element.formal_charge(bonds) {
+            Some(1) => label.push('⁺'),
+            Some(-1) => label.push('⁻'),
+            _ => {}
+        }
+        let color = element_color(element, visuals.text_color());
+        painter.text(
+            screen(atom),
+            Align2::CENTER_CENTER,
+            label,
+            font.clone(),
+            color,
+        );
+    }
+}
+
+/// Color of the element label (CPK-like)
+pub fn element_color(element: &Element, default: Color32) -> Color32 {
+    match element.symbol {
+        "N" => Color32::from_rgb(48, 80, 248),
+        "O" => Color32::from_rgb(255, 13, 13),
+        "S" => Color32::from_rgb(200, 160, 0),
+        "P" => Color32::from_rgb(255, 128, 0),
+        "F" | "Cl" => Color32::from_rgb(31, 200, 31),
+        "Br" => Color32::from_rgb(166, 41, 41),
+        "I" => Color32::from_rgb(148, 0, 148),
+        _ => default,
+    }
+}
+
+fn subscript(count: usize) -> String {
+    count
+        .to_string()
+        .chars()
+        // Subscript zero to nine
+        .filter_map(|digit| char::from_u32(0x2080 + digit.to_digit(10)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Distances of the bonded heavy atoms and the closest distance of the
+    /// others
+    fn distances(smiles: &str) -> (Vec<f32>, f32) {
+        let molecule = smiles.parse::<Molecule>().unwrap();
+        let positions = molecule.layout();
+        let heavy = molecule
+            .node_indices()
+            .filter(|&atom| molecule[atom] != H)
+            .collect::<Vec<_>>();
+        let mut bonds = Vec::new();
+        let mut closest = f32::MAX;
+        for (index, &lhs) in heavy.iter().enumerate() {
+            for &rhs in &heavy[index + 1..] {
+                let distance = positions[lhs.index()].distance(positions[rhs.index()]);
+                match molecule.find_edge(lhs, rhs) {
+                    Some(_) => bonds.push(distance),
+                    None => closest = closest.min(distance),
+                }
+            }
+        }
+        (bonds, closest)
+    }
+
+    #[test]
+    fn layout() {
+        for smiles in [
+            "CCCCCC",
+            "CC(C)(C)CC(C)C",
+            "c1ccccc1",
+            "Cc1ccccc1O",
+            "c1ccc2ccccc2c1",
+            "C1CCC2(CC1)CCCC2",
+            "OC(=O)CCCCCCC/C=C\\C/C=C\\CCCCC",
+            "CC#CC",
+            "C1CC1CC2CCCC2",
+        ] {
+            let (bonds, closest) = distances(smiles);
+            assert!(
+                bonds.iter().all(|distance| (distance - 1.0).abs() < 1e-3),
+                "{smiles}: {bonds:?}"
+            );
+            assert!(closest > 0.5, "{smiles}: {closest}");
+        }
+        // Zig-zag
+        let hexane = "CCCCCC".parse::<Molecule>().unwrap();
+        let positions = hexane.layout();
+        assert!((positions[0].distance(positions[2]) - 3f32.sqrt()).abs() < 1e-3);
+        assert!((positions[5].x - positions[0].x - 5.0 * 3f32.sqrt() / 2.0).abs() < 1e-3);
+        // Linear
+        let (_, closest) = distances("CC#CC");
+        assert!((closest - 2.0).abs() < 1e-3);
+        // Components side by side
+        let positions = "CCO.CC".parse::<Molecule>().unwrap().layout();
+        let right = positions[..3]
+            .iter()
+            .map(|position| position.x)
+            .fold(f32::MIN, f32::max);
+        let left = positions[3..5]
+            .iter()
+            .map(|position| position.x)
+            .fold(f32::MAX, f32::min);
+        assert!((left - right - GAP).abs() < 1e-3);
+        assert_eq!(subscript(12), "₁₂");
+    }
+
+    #[test]
+    fn cached() {
+        // Laid out once for the isomorphic molecules in another atom order
+        let ethanol = "CCO".parse::<Molecule>().unwrap();
+        let (key, ranks) = Canonicalizer.compute(&ethanol);
+        let depiction = Layouter.compute(LayouterKey {
+            key: &key,
+            molecule: &ethanol,
+            ranks: &ranks,
+        });
+        let relabelled = "OCC".parse::<Molecule>().unwrap();
+        let (other, ranks) = relabelled.canonical();
+        assert_eq!(other, key);
+        for edge in relabelled.edge_references() {
+            let (source, target) = (edge.source().index(), edge.target().index());
+            if relabelled[edge.source()] == H || relabelled[edge.target()] == H {
+                continue;
+            }
+            let distance =
+                depiction.positions[ranks[source]].distance(depiction.positions[ranks[target]]);
+            assert!((distance - 1.0).abs() < 1e-3);
+        }
+    }
+}
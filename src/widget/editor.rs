@@ -0,0 +1,618 @@
+//! Molecule editor: a canvas of the heavy-atom skeleton (the hydrogens are
+//! implicit).
+//!
+//! The atom tool adds atoms on the empty canvas, changes the element of the
+//! clicked atoms and drags them; the bond tool draws a bond of the current
+//! order from an atom to another atom (or to a new one), grows a chain from
+//! the clicked atom and cycles the order of the clicked bonds; the erase tool
+//! (or a secondary click) removes them. Every edit can be undone.
+
+use super::{
+    atom::{Element, BR, C, CL, F, H, I, N, O, P, S, SI},
+    depiction::element_color,
+    molecule::Molecule,
+};
+use crate::utils::SelectableValueFromIter;
+use egui::{
+    emath::Rot2, vec2, Align2, Button, FontId, Key, Pos2, Response, Sense, Shape, Stroke, Ui, Vec2,
+    Widget,
+};
+use petgraph::{
+    graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+/// Bond length (in points)
+const BOND: f32 = 32.0;
+
+/// Radius of an atom (in points) for the hit tests
+const RADIUS: f32 = 9.0;
+
+/// Width of the bond lines (in points)
+const WIDTH: f32 = 1.5;
+
+/// Editor widget
+pub fn editor(editor: &mut Editor) -> impl Widget + '_ {
+    move |ui: &mut Ui| editor.ui(ui)
+}
+
+/// Editing tool
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Tool {
+    #[default]
+    Atom,
+    Bond,
+    Erase,
+}
+
+impl Tool {
+    pub const ALL: [Self; 3] = [Self::Atom, Self::Bond, Self::Erase];
+}
+
+impl Display for Tool {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Atom => f.write_str("Atom"),
+            Self::Bond => f.write_str("Bond"),
+            Self::Erase => f.write_str("Erase"),
+        }
+    }
+}
+
+/// Molecule editor
+#[derive(Clone, Debug)]
+pub struct Editor {
+    /// Heavy-atom skeleton
+    pub molecule: Molecule,
+    /// Positions of the atoms (by their index, relative to the canvas)
+    pub positions: Vec<Pos2>,
+    pub tool: Tool,
+    /// Element of the new atoms
+    pub element: &'static Element,
+    /// Order of the new bonds
+    pub order: u8,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    /// Atom the drag started from (and the state before the drag)
+    dragged: Option<(NodeIndex, Snapshot)>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            molecule: Molecule::default(),
+            positions: Vec::new(),
+            tool: Tool::default(),
+            element: C,
+            order: 1,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            dragged: None,
+        }
+    }
+}
+
+impl Editor {
+    /// Adds an atom
+    pub fn add_atom(&mut self, position: Pos2, element: &'static Element) -> NodeIndex {
+        self.edit();
+        self.positions.push(position);
+        self.molecule.add_node(element)
+    }
+
+    /// Sets the element of the atom
+    pub fn set_element(&mut self, atom: NodeIndex, element: &'static Element) {
+        if self.molecule[atom] != element {
+            self.edit();
+            self.molecule[atom] = element;
+        }
+    }
+
+    /// Moves the atom
+    pub fn move_atom(&mut self, atom: NodeIndex, delta: Vec2) {
+        self.positions[atom.index()] += delta;
+    }
+
+    /// Removes the atom with its bonds
+    pub fn remove_atom(&mut self, atom: NodeIndex) {
+        self.edit();
+        // The graph moves its last node to the removed index
+        self.molecule.remove_node(atom);
+        self.positions.swap_remove(atom.index());
+    }
+
+    /// Bonds the atoms (sets the order of an existing bond)
+    pub fn bond(&mut self, lhs: NodeIndex, rhs: NodeIndex, order: u8) {
+        if lhs == rhs {
+            return;
+        }
+        match self.molecule.find_edge(lhs, rhs) {
+            Some(bond) if self.molecule[bond] == order => {}
+            Some(bond) => {
+                self.edit();
+                self.molecule[bond] = order;
+            }
+            None => {
+                self.edit();
+                self.molecule.add_edge(lhs, rhs, order);
+            }
+        }
+    }
+
+    /// Cycles the order of the bond (single, double, triple)
+    pub fn cycle_bond(&mut self, bond: EdgeIndex) {
+        self.edit();
+        self.molecule[bond] = self.molecule[bond] % 3 + 1;
+    }
+
+    pub fn remove_bond(&mut self, bond: EdgeIndex) {
+        self.edit();
+        self.molecule.remove_edge(bond);
+    }
+
+    /// Adds an atom bonded to the atom, away from its other neighbors (a
+    /// chain zig-zags)
+    pub fn grow(&mut self, atom: NodeIndex) -> NodeIndex {
+        let position = self.positions[atom.index()];
+        let neighbors = self
+            .molecule
+            .neighbors(atom)
+            .map(|neighbor| (self.positions[neighbor.index()] - position).normalized())
+            .collect::<Vec<_>>();
+        let direction = match &*neighbors {
+            // Zig-zag
+            [] => Rot2::from_angle(-30f32.to_radians()) * Vec2::X,
+            // The least crowded of the two directions at 120°
+            &[neighbor] => {
+                let crowding = |direction: Vec2| {
+                    let position = position + direction * BOND;
+                    self.positions
+                        .iter()
+                        .map(|other| 1.0 / other.distance_sq(position).max(1.0))
+                        .sum::<f32>()
+                };
+                let lhs = Rot2::from_angle(-120f32.to_radians()) * neighbor;
+                let rhs = Rot2::from_angle(120f32.to_radians()) * neighbor;
+                match crowding(rhs) < crowding(lhs) - 1e-6 {
+                    true => rhs,
+                    false => lhs,
+                }
+            }
+            neighbors => {
+                let sum = neighbors
+                    .iter()
+                    .fold(Vec2::ZERO, |sum, &vector| sum + vector);
+                match sum.length() < 0.1 {
+                    true => neighbors[0].rot90(),
+                    false => -sum.normalized(),
+                }
+            }
+        };
+        self.edit();
+        let (element, order) = (self.element, self.order);
+        self.positions.push(position + direction * BOND);
+        let new = self.molecule.add_node(element);
+        self.molecule.add_edge(atom, new, order);
+        new
+    }
+
+    /// Replaces the drawing by the heavy atoms of the molecule (laid out)
+    pub fn load(&mut self, molecule: &Molecule) {
+        self.edit();
+        let layout = molecule.layout();
+        let mut atoms = HashMap::new();
+        self.molecule = Molecule::default();
+        self.positions.clear();
+        for atom in molecule.node_indices().filter(|&atom| molecule[atom] != H) {
+            atoms.insert(atom, self.molecule.add_node(molecule[atom]));
+            self.positions.push(layout[atom.index()]);
+        }
+        for edge in molecule.edge_references() {
+            if let (Some(&source), Some(&target)) =
+                (atoms.get(&edge.source()), atoms.get(&edge.target()))
+            {
+                self.molecule.add_edge(source, target, *edge.weight());
+            }
+        }
+        let min = self
+            .positions
+            .iter()
+            .fold(Pos2::new(f32::MAX, f32::MAX), |min, &position| {
+                min.min(position)
+            });
+        for position in &mut self.positions {
+            *position = Pos2::new(2.0 * RADIUS, 2.0 * RADIUS) + (*position - min) * BOND;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        if self.molecule.node_count() != 0 {
+            self.edit();
+            self.molecule.clear();
+            self.positions.clear();
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo.pop() {
+            self.redo.push(self.snapshot());
+            self.restore(snapshot);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.redo.pop() {
+            self.undo.push(self.snapshot());
+            self.restore(snapshot);
+        }
+    }
+
+    /// Molecule completed with the implicit hydrogens
+    pub fn completed(&self) -> Molecule {
+        let mut molecule = self.molecule.clone();
+        molecule.add_hydrogens();
+        molecule
+    }
+
+    /// Atom under the position
+    pub fn atom_at(&self, position: Pos2) -> Option<NodeIndex> {
+        self.molecule
+            .node_indices()
+            .map(|atom| (atom, self.positions[atom.index()].distance(position)))
+            .filter(|&(_, distance)| distance < RADIUS)
+            .min_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1))
+            .map(|(atom, _)| atom)
+    }
+
+    /// Bond under the position
+    pub fn bond_at(&self, position: Pos2) -> Option<EdgeIndex> {
+        self.molecule
+            .edge_references()
+            .map(|edge| {
+                let from = self.positions[edge.source().index()];
+                let to = self.positions[edge.target().index()];
+                (edge.id(), distance(position, from, to))
+            })
+            .filter(|&(_, distance)| distance < RADIUS / 2.0)
+            .min_by(|lhs, rhs| lhs.1.total_cmp(&rhs.1))
+            .map(|(bond, _)| bond)
+    }
+
+    fn ui(&mut self, ui: &mut Ui) -> Response {
+        // Toolbar
+        ui.horizontal(|ui| {
+            ui.selectable_value_from_iter(&mut self.tool, Tool::ALL.into_iter());
+            ui.separator();
+            for order in 1..=3 {
+                ui.selectable_value(&mut self.order, order, ["—", "=", "≡"][order as usize - 1])
+                    .on_hover_text("the order of the new bonds");
+            }
+            ui.separator();
+            if ui
+                .add_enabled(!self.undo.is_empty(), Button::new("⟲"))
+                .on_hover_text("undo (Ctrl+Z)")
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(!self.redo.is_empty(), Button::new("⟳"))
+                .on_hover_text("redo (Ctrl+Y)")
+                .clicked()
+            {
+                self.redo();
+            }
+            if ui.button("🗑").on_hover_text("clear").clicked() {
+                self.clear();
+            }
+        });
+        ui.horizontal_wrapped(|ui| {
+            // The element of the new atoms
+            for element in [C, N, O, S, P, F, CL, BR, I, SI, H] {
+                ui.selectable_value(&mut self.element, element, element.symbol)
+                    .on_hover_text(element.name);
+            }
+        });
+        // Canvas
+        let size = vec2(ui.available_width(), ui.available_width().min(320.0));
+        let (rect, mut response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+        let origin = rect.min.to_vec2();
+        let pointer = response
+            .interact_pointer_pos()
+            .map(|pointer| pointer - origin);
+        let hovered = response.hover_pos().map(|pointer| pointer - origin);
+        if response.hovered() {
+            let (undo, redo) = ui.input(|input| {
+                let command = input.modifiers.command;
+                (
+                    command && !input.modifiers.shift && input.key_pressed(Key::Z),
+                    command
+                        && (input.key_pressed(Key::Y)
+                            || input.modifiers.shift && input.key_pressed(Key::Z)),
+                )
+            });
+            if undo {
+                self.undo();
+                response.mark_changed();
+            }
+            if redo {
+                self.redo();
+                response.mark_changed();
+            }
+        }
+        if response.drag_started() {
+            let atom = pointer.and_then(|pointer| self.atom_at(pointer));
+            self.dragged = atom.map(|atom| (atom, self.snapshot()));
+        }
+        if let Some((atom, _)) = self.dragged {
+            if response.dragged() && self.tool == Tool::Atom {
+                self.move_atom(atom, response.drag_delta());
+                response.mark_changed();
+            }
+        }
+        if response.drag_released() {
+            if let Some((atom, snapshot)) = self.dragged.take() {
+                match self.tool {
+                    Tool::Atom if snapshot.positions != self.positions => {
+                        self.undo.push(snapshot);
+                        self.redo.clear();
+                    }
+                    Tool::Bond => {
+                        if let Some(pointer) = pointer {
+                            if self.positions[atom.index()].distance(pointer) > RADIUS {
+                                let target = match self.atom_at(pointer) {
+                                    Some(target) => target,
+                                    None => self.add_atom(pointer, self.element),
+                                };
+                                self.bond(atom, target, self.order);
+                                response.mark_changed();
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if response.clicked() {
+            if let Some(pointer) = pointer {
+                let atom = self.atom_at(pointer);
+                let bond = self.bond_at(pointer);
+                match (self.tool, atom, bond) {
+                    (Tool::Atom, Some(atom), _) => self.set_element(atom, self.element),
+                    (Tool::Atom, None, _) => {
+                        self.add_atom(pointer, self.element);
+                    }
+                    (Tool::Bond, Some(atom), _) => {
+                        self.grow(atom);
+                    }
+                    (Tool::Bond, None, Some(bond)) => self.cycle_bond(bond),
+                    (Tool::Erase, Some(atom), _) => self.remove_atom(atom),
+                    (Tool::Erase, None, Some(bond)) => self.remove_bond(bond),
+                    _ => {}
+                }
+                response.mark_changed();
+            }
+        }
+        if response.secondary_clicked() {
+            if let Some(pointer) = pointer {
+                match (self.atom_at(pointer), self.bond_at(pointer)) {
+                    (Some(atom), _) => self.remove_atom(atom),
+                    (None, Some(bond)) => self.remove_bond(bond),
+                    _ => {}
+                }
+                response.mark_changed();
+            }
+        }
+        // Paint
+        if ui.is_rect_visible(rect) {
+            let completed = self.completed();
+            let violations = completed.violations();
+            let visuals = ui.visuals();
+            let painter = ui.painter_at(rect);
+            painter.rect_filled(
+                rect,
+                visuals.widgets.noninteractive.rounding,
+                visuals.extreme_bg_color,
+            );
+            let stroke = Stroke::new(WIDTH, visuals.text_color());
+            let screen = |atom: NodeIndex| rect.min + self.positions[atom.index()].to_vec2();
+            let labeled = |atom: NodeIndex| {
+                self.molecule[atom] != C || self.molecule.neighbors(atom).next().is_none()
+            };
+            for edge in self.molecule.edge_references() {
+                let (mut from, mut to) = (screen(edge.source()), screen(edge.target()));
+                let direction = (to - from).normalized();
+                // Shortened at the labels
+                if labeled(edge.source()) {
+                    from += direction * RADIUS;
+                }
+                if labeled(edge.target()) {
+                    to -= direction * RADIUS;
+                }
+                let normal = direction.rot90() * 3.0;
+                let offsets: &[f32] = match *edge.weight() {
+                    2 => &[-1.0, 1.0],
+                    3 => &[-1.5, 0.0, 1.5],
+                    _ => &[0.0],
+                };
+                for &offset in offsets {
+                    painter.line_segment([from + normal * offset, to + normal * offset], stroke);
+                }
+            }
+            for atom in self.molecule.node_indices() {
+                let center = screen(atom);
+                let invalid = violations.iter().any(|violation| violation.atom == atom);
+                if invalid {
+                    painter.circle_stroke(
+                        center,
+                        RADIUS,
+                        Stroke::new(WIDTH, visuals.error_fg_color),
+                    );
+                }
+                if labeled(atom) {
+                    painter.text(
+                        center,
+                        Align2::CENTER_CENTER,
+                        self.molecule[atom].symbol,
+                        FontId::proportional(14.0),
+                        element_color(self.molecule[atom], visuals.text_color()),
+                    );
+                }
+            }
+            // Hovered atom and the bond being drawn
+            let highlight = Stroke::new(1.0_f32, visuals.selection.stroke.color);
+            if let Some(atom) = hovered.and_then(|pointer| self.atom_at(pointer)) {
+                painter.circle_stroke(screen(atom), RADIUS, highlight);
+            } else if let Some(bond) = hovered.and_then(|pointer| self.bond_at(pointer)) {
+                if let Some((lhs, rhs)) = self.molecule.edge_endpoints(bond) {
+                    painter.line_segment(
+                        [screen(lhs), screen(rhs)],
+                        Stroke::new(5.0_f32, highlight.color.linear_multiply(0.3)),
+                    );
+                }
+            }
+            if let (Tool::Bond, Some((atom, _)), Some(pointer)) =
+                (self.tool, &self.dragged, pointer)
+            {
+                painter.extend(Shape::dashed_line(
+                    &[screen(*atom), rect.min + pointer.to_vec2()],
+                    highlight,
+                    4.0,
+                    2.0,
+                ));
+            }
+            if self.molecule.node_count() == 0 {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "Click to add an atom",
+                    FontId::proportional(14.0),
+                    visuals.weak_text_color(),
+                );
+            }
+            // Status
+            let formula = completed.formula();
+            if self.molecule.node_count() != 0 {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Formula: {formula}"));
+                    ui.separator();
+                    ui.label(format!(
+                        "Mass: {} ({:.4})",
+                        formula.nominal(),
+                        formula.monoisotopic()
+                    ));
+                    if let Some(charge) = completed.charge().filter(|&charge| charge != 0) {
+                        ui.separator();
+                        ui.label(format!("Charge: {charge:+}"));
+                    }
+                });
+            }
+            for violation in &violations {
+                ui.colored_label(ui.visuals().error_fg_color, violation.to_string());
+            }
+        }
+        response
+    }
+
+    /// Marks an edit (the state before it can be restored)
+    fn edit(&mut self) {
+        self.undo.push(self.snapshot());
+        self.redo.clear();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            molecule: self.molecule.clone(),
+            positions: self.positions.clone(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.molecule = snapshot.molecule;
+        self.positions = snapshot.positions;
+        self.dragged = None;
+    }
+}
+
+/// State of the editor
+#[derive(Clone, Debug)]
+struct Snapshot {
+    molecule: Molecule,
+    positions: Vec<Pos2>,
+}
+
+/// Distance from the point to the segment
+fn distance(point: Pos2, from: Pos2, to: Pos2) -> f32 {
+    let segment = to - from;
+    let length = segment.length_sq();
+    if length == 0.0 {
+        return point.distance(from);
+    }
+    let t = ((point - from).dot(segment) / length).clamp(0.0, 1.0);
+    point.distance(from + segment * t)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use egui::pos2;
+
+    #[test]
+    fn edit() {
+        let mut editor = Editor::default();
+        let c1 = editor.add_atom(pos2(0.0, 0.0), C);
+        let c2 = editor.grow(c1);
+        let c3 = editor.grow(c2);
+        assert!((editor.positions[c2.index()].distance(pos2(0.0, 0.0)) - BOND).abs() < 1e-3);
+        // Zig-zag
+        assert!(editor.positions[c3.index()].y.abs() < 1e-3);
+        editor.set_element(c3, O);
+        assert_eq!(editor.completed().smiles(), "CCO");
+        assert_eq!(editor.completed().formula().to_string(), "C2H6O");
+        let bond = editor.molecule.find_edge(c1, c2).unwrap();
+        editor.cycle_bond(bond);
+        editor.cycle_bond(bond);
+        editor.cycle_bond(bond);
+        assert_eq!(editor.molecule[bond], 1);
+        editor.set_element(c1, N);
+        editor.bond(c1, c2, 2);
+        assert_eq!(editor.completed().smiles(), "N=CO");
+        // The last atom takes the index of the removed one
+        let position = editor.positions[c3.index()];
+        editor.remove_atom(c1);
+        assert_eq!(editor.molecule[c1], O);
+        assert_eq!(editor.positions[c1.index()], position);
+        assert_eq!(editor.completed().smiles(), "CO");
+        editor.undo();
+        assert_eq!(editor.completed().smiles(), "N=CO");
+        editor.undo();
+        editor.undo();
+        assert_eq!(editor.completed().smiles(), "CCO");
+        editor.redo();
+        editor.redo();
+        assert_eq!(editor.completed().smiles(), "N=CO");
+        editor.bond(c2, c3, 3);
+        assert_eq!(editor.completed().violations().len(), 2);
+        editor.undo();
+        editor.clear();
+        assert_eq!(editor.molecule.node_count(), 0);
+        editor.undo();
+        assert_eq!(editor.molecule.node_count(), 3);
+        // Hit tests
+        let position = editor.positions[c2.index()];
+        assert_eq!(editor.atom_at(position + vec2(2.0, 2.0)), Some(c2));
+        let middle = position + (editor.positions[c3.index()] - position) / 2.0;
+        assert_eq!(editor.bond_at(middle), editor.molecule.find_edge(c2, c3));
+        assert_eq!(editor.atom_at(pos2(-100.0, -100.0)), None);
+        let phenol = "Oc1ccccc1".parse::<Molecule>().unwrap();
+        editor.load(&phenol);
+        assert_eq!(editor.molecule.node_count(), 7);
+        assert_eq!(editor.completed().smiles(), phenol.smiles());
+        editor.undo();
+        assert_eq!(editor.molecule.node_count(), 3);
+    }
+}
@@ -1,20 +1,26 @@
-use super::atom::{C, H};
+use super::atom::{Element, C, H};
+use crate::formula::{Atom, Formula};
 use num_traits::Num;
 use petgraph::{
     algo::is_isomorphic_subgraph_matching,
     graph::{EdgeIndex, NodeIndex},
     prelude::Graph,
+    visit::EdgeRef,
     Undirected,
 };
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+};
 
 // type Index = NodeIndex<DefaultIx>;
 
 /// Molecule
-#[derive(Clone, Debug, Default)]
-pub struct Molecule<N = f64, E = u8>(Graph<N, E, Undirected>);
+#[derive(Clone, Debug)]
+pub struct Molecule<N = &'static Element, E = u8>(Graph<N, E, Undirected>);
 
-impl<E: Num> Molecule<f64, E> {
+impl<E: Num> Molecule<&'static Element, E> {
     pub fn new() -> Self {
         Molecule(Graph::new_undirected())
     }
@@ -27,11 +33,53 @@ impl<E: Num> Molecule<f64, E> {
         self.node_indices().filter(move |&index| self[index] == C)
     }
 
+    /// Adds the bonds between the atoms (the graph's `extend_with_edges`
+    /// requires a default node weight, which an element has not)
+    pub fn extend_with_bonds(&mut self, bonds: &[(NodeIndex, NodeIndex, E)])
+    where
+        E: Clone,
+    {
+        for (a, b, bond) in bonds {
+            self.add_edge(*a, *b, bond.clone());
+        }
+    }
+
+    /// Molecular formula
+    pub fn formula(&self) -> Formula {
+        let mut atoms = BTreeMap::new();
+        for index in self.node_indices() {
+            let atom = Atom {
+                element: self[index],
+                mass_number: None,
+            };
+            *atoms.entry(atom).or_default() += 1;
+        }
+        Formula { atoms, charge: 0 }
+    }
+
     pub fn is_isomorphic(&self, other: &Self) -> bool {
         is_isomorphic_subgraph_matching(&self.0, &other.0, PartialEq::eq, PartialEq::eq)
     }
 }
 
+/// Hash of the atoms and the bonds (in their order)
+impl Hash for Molecule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for index in self.node_indices() {
+            self[index].symbol.hash(state);
+        }
+        for edge in self.edge_references() {
+            (edge.source(), edge.target(), edge.weight()).hash(state);
+        }
+    }
+}
+
+impl<N, E> Default for Molecule<N, E> {
+    fn default() -> Self {
+        Self(Graph::default())
+    }
+}
+
 impl<N, E> Deref for Molecule<N, E> {
     type Target = Graph<N, E, Undirected>;
 
@@ -55,7 +103,7 @@ mod alkanes {
         let mut molecule = Molecule::new();
         let c: [_; 1] = from_fn(|_| molecule.add_node(C));
         let h: [_; 4] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (h[0], c[0], 1),
             (h[1], c[0], 1),
             (h[2], c[0], 1),
@@ -69,7 +117,7 @@ mod alkanes {
         let mut molecule = Molecule::new();
         let c: [_; 2] = from_fn(|_| molecule.add_node(C));
         let h: [_; 6] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 1),
             (h[0], c[0], 1),
             (h[1], c[0], 1),
@@ -86,7 +134,7 @@ mod alkanes {
         let mut molecule = Molecule::new();
         let c: [_; 3] = from_fn(|_| molecule.add_node(C));
         let h: [_; 8] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 1),
             (c[1], c[2], 1),
             (h[0], c[0], 1),
@@ -100,14 +148,6 @@ mod alkanes {
         ]);
         molecule
     }
-
-    // Butane
-    // Pentane
-    // Hexane
-    // Heptane
-    // Octane
-    // Nonane
-    // Decane
 }
 
 mod alkenes {
@@ -119,7 +159,7 @@ mod alkenes {
         let mut molecule = Molecule::new();
         let c: [_; 2] = from_fn(|_| molecule.add_node(C));
         let h: [_; 4] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 2),
             (h[0], c[0], 1),
             (h[1], c[0], 1),
@@ -134,7 +174,7 @@ mod alkenes {
         let mut molecule = Molecule::new();
         let c: [_; 3] = from_fn(|_| molecule.add_node(C));
         let h: [_; 6] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 2),
             (c[1], c[2], 1),
             (h[0], c[0], 1),
@@ -152,7 +192,7 @@ mod alkenes {
         let mut molecule = Molecule::new();
         let c: [_; 4] = from_fn(|_| molecule.add_node(C));
         let h: [_; 8] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 2),
             (c[1], c[2], 1),
             (c[2], c[3], 1),
@@ -173,7 +213,7 @@ mod alkenes {
         let mut molecule = Molecule::new();
         let c: [_; 5] = from_fn(|_| molecule.add_node(C));
         let h: [_; 10] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             (c[0], c[1], 2),
             (c[1], c[2], 1),
             (c[2], c[3], 1),
@@ -192,12 +232,6 @@ mod alkenes {
         molecule
     }
 
-    // Hexene (C₆H₁₂
-    // Heptene (C₇H₁₄
-    // Octene (C₈H₁₆
-    // Nonene (C₉H₁₈
-    // Decene (C₁₀H₂₀
-
     #[test]
     fn bounds() {
         for molecule in [ethene(), propene(), butene(), pentene()] {
@@ -219,9 +253,12 @@ mod alkenes {
 #[cfg(test)]
 mod test {
     use super::{
+        super::series::{alkane, alkene},
+        alkanes::{ethane, methane, propane},
         alkenes::{butene, ethene, pentene, propene},
         Molecule, C, H,
     };
+    use crate::lipid::Position;
     use std::array::from_fn;
 
     #[test]
@@ -236,12 +273,39 @@ mod test {
         }
     }
 
+    #[test]
+    fn series() {
+        let position = Position {
+            carbon: 1,
+            geometry: None,
+        };
+        for (carbons, molecule) in [(1, methane()), (2, ethane()), (3, propane())] {
+            assert!(molecule.is_isomorphic(&alkane(carbons).unwrap()));
+        }
+        for (carbons, molecule) in [(2, ethene()), (3, propene()), (4, butene()), (5, pentene())] {
+            assert!(molecule.is_isomorphic(&alkene(carbons, &[position]).unwrap()));
+        }
+    }
+
+    #[test]
+    fn smiles() {
+        let equal =
+            |lhs: &Molecule, smiles: &str| lhs.is_isomorphic(&smiles.parse::<Molecule>().unwrap());
+        assert!(equal(&methane(), "C"));
+        assert!(equal(&propene(), "C=CC"));
+        assert!(equal(&pentene(), "C=CCCC"));
+        assert!(!equal(&pentene(), "CC=CCC"));
+        assert_eq!(methane().smiles(), "C");
+        assert_eq!(propene().smiles(), "C=CC");
+        assert_eq!(pentene().smiles(), "C=CCCC");
+    }
+
     #[test]
     fn test() {
         let mut molecule = Molecule::new();
         let c: [_; 1] = from_fn(|_| molecule.add_node(C));
         let h: [_; 1] = from_fn(|_| molecule.add_node(H));
-        molecule.extend_with_edges(&[
+        molecule.extend_with_bonds(&[
             // (c[0], h[0], 1),
             (h[0], c[0], 1),
             // (h[1], c[0], 1),
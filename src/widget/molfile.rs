@@ -0,0 +1,568 @@
+//! MDL Molfile (V2000 and V3000) and SD file records.
+//!
+//! Atoms without explicit hydrogens get their implicit ones (charged atoms
+//! none). Charges aren't kept, since an atom is only its element: they are
+//! written from the formal charges of the bonds. The coordinates are kept on
+//! the record (the added hydrogens at their heavy atom); a molecule without
+//! coordinates is written laid out.
+
+use super::{
+    atom::{Element, H},
+    molecule::Molecule,
+    smiles::kekulize,
+};
+use crate::parser::Parsed;
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+use std::fmt::Write;
+
+/// Bond length of the laid out molecules (in Å)
+const BOND_LENGTH: f64 = 1.5;
+
+/// Structure record of a Molfile or an SD file
+#[derive(Clone, Debug, Default)]
+pub struct Record {
+    pub molecule: Molecule,
+    /// Metadata of the data fields (and the header name)
+    pub parsed: Parsed,
+    /// Data fields not mapped to the metadata
+    pub fields: Vec<(String, String)>,
+    /// Atom coordinates (empty if the file has none)
+    pub coordinates: Vec<[f64; 3]>,
+}
+
+impl Record {
+    /// Records of an SD file (or the single one of a Molfile)
+    pub fn read(input: &str) -> Result<Vec<Self>> {
+        let input = input.replace("\r\n", "\n");
+        let mut records = Vec::new();
+        for (index, chunk) in input.split("$$$$").enumerate() {
+            if chunk.trim().is_empty() {
+                continue;
+            }
+            // The rest of the `$$$$` line
+            let chunk = match index {
+                0 => chunk,
+                _ => chunk.split_once('\n').map_or(chunk, |(_, chunk)| chunk),
+            };
+            let record = Self::parse(chunk).with_context(|| format!("record {index}"))?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    fn parse(input: &str) -> Result<Self> {
+        let lines = input.lines().collect::<Vec<_>>();
+        ensure!(lines.len() >= 4, "missing header");
+        let end = lines
+            .iter()
+            .position(|line| line.starts_with("M  END"))
+            .context("missing `M  END`")?;
+        let (molecule, coordinates) = if lines[3].contains("V3000") {
+            v3000(&lines[4..end])?
+        } else {
+            v2000(&lines[3..end])?
+        };
+        let mut record = Self {
+            molecule,
+            coordinates,
+            ..Default::default()
+        };
+        record.parsed.name = lines[0].trim().to_owned();
+        let mut lines = lines[end + 1..].iter();
+        while let Some(line) = lines.next() {
+            let Some(name) = line
+                .strip_prefix('>')
+                .and_then(|line| line.split_once('<'))
+                .and_then(|(_, line)| line.split_once('>'))
+                .map(|(name, _)| name)
+            else {
+                continue;
+            };
+            let mut value = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim().is_empty() {
+                    break;
+                }
+                value.push(line.trim_end());
+            }
+            record.field(name, value.join("\n"));
+        }
+        if record.parsed.formula.is_empty() {
+            record.parsed.formula = record.molecule.formula().to_string();
+        }
+        Ok(record)
+    }
+
+    /// Maps a data field to the metadata
+    fn field(&mut self, name: &str, value: String) {
+        let key = name
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_uppercase();
+        let parsed = &mut self.parsed;
+        match &*key {
+            "NAME" | "COMPOUNDNAME" | "COMMONNAME" => parsed.name = value,
+            "CAS" | "CASNO" | "CASNUMBER" | "CASRN" => {
+                parsed.cas = value
+                    .chars()
+                    .filter(char::is_ascii_digit)
+                    .collect::<String>()
+                    .parse()
+                    .ok()
+            }
+            "FORMULA" | "MOLECULARFORMULA" => parsed.formula = value,
+            "MW" | "MOLECULARWEIGHT" => {
+                parsed.mw = value.trim().parse::<f64>().ok().map(|mw| mw.round() as _)
+            }
+            "NIST" | "NISTNO" => parsed.nist = value.trim().parse().ok(),
+            "DB" | "DBNO" => parsed.db = value.trim().parse().ok(),
+            "SYNONYM" | "SYNONYMS" => parsed.synonym = value,
+            "COMMENT" | "COMMENTS" => parsed.comments = value,
+            _ => self.fields.push((name.to_owned(), value)),
+        }
+    }
+
+    /// The record describes the spectrum (same CAS number, or same name if
+    /// either has none)
+    pub fn matches(&self, parsed: &Parsed) -> bool {
+        match (self.parsed.cas, parsed.cas) {
+            (Some(lhs), Some(rhs)) => lhs == rhs,
+            _ => {
+                let name = self.parsed.name.trim();
+                !name.is_empty() && name.eq_ignore_ascii_case(parsed.name.trim())
+            }
+        }
+    }
+
+    /// SD file record
+    pub fn write(&self, version: Version) -> String {
+        // Laid out if the coordinates don't cover the atoms
+        let mut sdf = match self.coordinates.len() == self.molecule.node_count() {
+            false => self.molecule.molfile(&self.parsed.name, version),
+            true => molfile(
+                &self.molecule,
+                &self.parsed.name,
+                version,
+                &self.coordinates,
+            ),
+        };
+        let parsed = &self.parsed;
+        let mut fields = Vec::new();
+        if !parsed.name.is_empty() {
+            fields.push(("NAME".to_owned(), parsed.name.clone()));
+        }
+        if let Some(cas) = parsed.cas {
+            fields.push(("CAS".to_owned(), cas_number(cas)));
+        }
+        if !parsed.formula.is_empty() {
+            fields.push(("FORMULA".to_owned(), parsed.formula.clone()));
+        }
+        if let Some(mw) = parsed.mw {
+            fields.push(("MW".to_owned(), mw.to_string()));
+        }
+        if let Some(nist) = parsed.nist {
+            fields.push(("NIST".to_owned(), nist.to_string()));
+        }
+        if let Some(db) = parsed.db {
+            fields.push(("DB".to_owned(), db.to_string()));
+        }
+        if !parsed.synonym.is_empty() {
+            fields.push(("SYNONYMS".to_owned(), parsed.synonym.clone()));
+        }
+        if !parsed.comments.is_empty() {
+            fields.push(("COMMENTS".to_owned(), parsed.comments.clone()));
+        }
+        for (name, value) in fields.iter().chain(&self.fields) {
+            write!(sdf, "> <{name}>\n{value}\n\n").ok();
+        }
+        sdf.push_str("$$$$\n");
+        sdf
+    }
+}
+
+/// Molfile version
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Version {
+    #[default]
+    V2000,
+    V3000,
+}
+
+impl Molecule {
+    /// Molfile (all atoms, hydrogens included, laid out)
+    pub fn molfile(&self, name: &str, version: Version) -> String {
+        let coordinates = self
+            .layout()
+            .into_iter()
+            .map(|position| {
+                let (x, y) = (position.x as f64, position.y as f64);
+                [x * BOND_LENGTH, -y * BOND_LENGTH, 0.0]
+            })
+            .collect::<Vec<_>>();
+        molfile(self, name, version, &coordinates)
+    }
+}
+
+/// Molfile of the molecule at the coordinates
+fn molfile(molecule: &Molecule, name: &str, version: Version, coordinates: &[[f64; 3]]) -> String {
+    let mut molfile = format!("{name}\n  msp\n\n");
+    let bonds = molecule
+        .edge_references()
+        .map(|edge| {
+            (
+                edge.source().index() + 1,
+                edge.target().index() + 1,
+                *edge.weight(),
+            )
+        })
+        .collect::<Vec<_>>();
+    match version {
+        Version::V2000 => {
+            writeln!(
+                molfile,
+                "{:>3}{:>3}  0  0  0  0  0  0  0  0999 V2000",
+                molecule.node_count(),
+                bonds.len(),
+            )
+            .ok();
+            for index in molecule.node_indices() {
+                let [x, y, z] = coordinates[index.index()];
+                writeln!(
+                    molfile,
+                    "{x:>10.4}{y:>10.4}{z:>10.4} {:<3} 0  0  0  0  0  0  0  0  0  0  0  0",
+                    molecule[index].symbol,
+                )
+                .ok();
+            }
+            for (lhs, rhs, order) in bonds {
+                writeln!(molfile, "{lhs:>3}{rhs:>3}{order:>3}  0  0  0  0").ok();
+            }
+            // Non-zero formal charges of the (one-based) atoms, at most eight
+            // per line
+            let charges = molecule.node_indices().filter_map(|index| {
+                let charge = molecule.formal_charge(index)?;
+                (charge != 0).then_some((index.index() + 1, charge))
+            });
+            for charges in &charges.chunks(8) {
+                let charges = charges.collect::<Vec<_>>();
+                write!(molfile, "M  CHG{:>3}", charges.len()).ok();
+                for (atom, charge) in charges {
+                    write!(molfile, " {atom:>3} {charge:>3}").ok();
+                }
+                molfile.push('\n');
+            }
+        }
+        Version::V3000 => {
+            molfile.push_str("  0  0  0     0  0            999 V3000\n");
+            molfile.push_str("M  V30 BEGIN CTAB\n");
+            writeln!(
+                molfile,
+                "M  V30 COUNTS {} {} 0 0 0",
+                molecule.node_count(),
+                bonds.len()
+            )
+            .ok();
+            molfile.push_str("M  V30 BEGIN ATOM\n");
+            for index in molecule.node_indices() {
+                let symbol = molecule[index].symbol;
+                let [x, y, z] = coordinates[index.index()];
+                write!(
+                    molfile,
+                    "M  V30 {} {symbol} {x:.4} {y:.4} {z:.4} 0",
+                    index.index() + 1,
+                )
+                .ok();
+                match molecule.formal_charge(index) {
+                    Some(charge) if charge != 0 => writeln!(molfile, " CHG={charge}").ok(),
+                    _ => writeln!(molfile).ok(),
+                };
+            }
+            molfile.push_str("M  V30 END ATOM\n");
+            molfile.push_str("M  V30 BEGIN BOND\n");
+            for (index, (lhs, rhs, order)) in bonds.into_iter().enumerate() {
+                writeln!(molfile, "M  V30 {} {order} {lhs} {rhs}", index + 1).ok();
+            }
+            molfile.push_str("M  V30 END BOND\n");
+            molfile.push_str("M  V30 END CTAB\n");
+        }
+    }
+    molfile.push_str("M  END\n");
+    molfile
+}
+
+/// Counts line, atom block, bond block and properties of a V2000 connection
+/// table
+fn v2000(lines: &[&str]) -> Result<(Molecule, Vec<[f64; 3]>)> {
+    let counts = lines.first().context("missing counts line")?;
+    let column = |line: &str, range: std::ops::Range<usize>| -> Result<usize> {
+        Ok(line.get(range).unwrap_or_default().trim().parse()?)
+    };
+    let atoms = column(counts, 0..3)?;
+    let bonds = column(counts, 3..6)?;
+    ensure!(lines.len() > atoms + bonds, "truncated connection table");
+    let mut builder = Builder::default();
+    for line in &lines[1..=atoms] {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let symbol = fields.get(3).context("missing atom symbol")?;
+        // Charge code: 1-3 positive, 5-7 negative
+        let charged = fields
+            .get(5)
+            .is_some_and(|&code| !matches!(code, "0" | "4"));
+        builder.atom(symbol, charged, coordinates(&fields[..3])?)?;
+    }
+    for line in &lines[atoms + 1..=atoms + bonds] {
+        builder.bond(
+            column(line, 0..3)?,
+            column(line, 3..6)?,
+            column(line, 6..9)? as u8,
+        )?;
+    }
+    for line in &lines[atoms + bonds + 1..] {
+        if let Some(charges) = line.strip_prefix("M  CHG") {
+            let values = charges.split_whitespace().skip(1).collect::<Vec<_>>();
+            for pair in values.chunks(2) {
+                if let [atom, charge] = pair {
+                    let atom = atom.parse::<usize>()?;
+                    if *charge != "0" {
+                        builder.charge(atom)?;
+                    }
+                }
+            }
+        }
+    }
+    builder.build()
+}
+
+/// Connection table of a V3000 molfile
+fn v3000(lines: &[&str]) -> Result<(Molecule, Vec<[f64; 3]>)> {
+    let mut joined = Vec::<String>::new();
+    let mut continued = false;
+    for line in lines {
+        let Some(line) = line.strip_prefix("M  V30 ") else {
+            continue;
+        };
+        let (line, next) = match line.trim_end().strip_suffix('-') {
+            Some(line) => (line, true),
+            None => (line.trim_end(), false),
+        };
+        match joined.last_mut() {
+            Some(last) if continued => last.push_str(line),
+            _ => joined.push(line.to_owned()),
+        }
+        continued = next;
+    }
+    let mut builder = Builder::default();
+    let mut block = "";
+    for line in &joined {
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match fields[..] {
+            ["BEGIN", name, ..] => block = name,
+            ["END", ..] => block = "",
+            [_, symbol, x, y, z, ..] if block == "ATOM" => {
+                let charged = fields
+                    .iter()
+                    .any(|field| field.starts_with("CHG=") && *field != "CHG=0");
+                builder.atom(symbol, charged, coordinates(&[x, y, z])?)?;
+            }
+            [_, order, lhs, rhs, ..] if block == "BOND" => {
+                builder.bond(lhs.parse()?, rhs.parse()?, order.parse()?)?;
+            }
+            _ => {}
+        }
+    }
+    builder.build()
+}
+
+/// Coordinates of the atom fields
+fn coordinates(fields: &[&str]) -> Result<[f64; 3]> {
+    let mut coordinates = [0.0; 3];
+    for (coordinate, field) in coordinates.iter_mut().zip(fields) {
+        *coordinate = field
+            .parse()
+            .with_context(|| format!("atom coordinate {field}"))?;
+    }
+    Ok(coordinates)
+}
+
+/// Builder of a molecule from the atom and bond blocks
+#[derive(Default)]
+struct Builder {
+    molecule: Molecule,
+    charged: Vec<bool>,
+    aromatic: Vec<(NodeIndex, NodeIndex)>,
+    coordinates: Vec<[f64; 3]>,
+}
+
+impl Builder {
+    fn atom(&mut self, symbol: &str, charged: bool, coordinates: [f64; 3]) -> Result<()> {
+        let Some(element) = Element::find(symbol) else {
+            bail!("unknown element {symbol}");
+        };
+        self.molecule.add_node(element);
+        self.charged.push(charged);
+        self.coordinates.push(coordinates);
+        Ok(())
+    }
+
+    /// Bond between the (one-based) atoms
+    fn bond(&mut self, lhs: usize, rhs: usize, order: u8) -> Result<()> {
+        let count = self.molecule.node_count();
+        ensure!(
+            (1..=count).contains(&lhs) && (1..=count).contains(&rhs) && lhs != rhs,
+            "bond between atoms {lhs} and {rhs}",
+        );
+        let (lhs, rhs) = (NodeIndex::new(lhs - 1), NodeIndex::new(rhs - 1));
+        match order {
+            1..=3 => {
+                self.molecule.add_edge(lhs, rhs, order);
+            }
+            4 => {
+                self.molecule.add_edge(lhs, rhs, 1);
+                self.aromatic.push((lhs, rhs));
+            }
+            _ => bail!("unsupported bond type {order}"),
+        }
+        Ok(())
+    }
+
+    fn charge(&mut self, atom: usize) -> Result<()> {
+        match self.charged.get_mut(atom.wrapping_sub(1)) {
+            Some(charged) => *charged = true,
+            None => bail!("charge of a missing atom {atom}"),
+        }
+        Ok(())
+    }
+
+    /// Molecule and its coordinates (empty if all atoms are at the origin)
+    fn build(mut self) -> Result<(Molecule, Vec<[f64; 3]>)> {
+        let hydrogens = vec![0; self.molecule.node_count()];
+        ensure!(
+            kekulize(&mut self.molecule, &self.aromatic, &hydrogens),
+            "aromatic system can't be kekulized",
+        );
+        let atoms = self.molecule.node_indices().collect::<Vec<_>>();
+        for index in atoms {
+            if self.molecule[index] == H || self.charged[index.index()] {
+                continue;
+            }
+            for _ in 0..self.molecule.implicit_hydrogens(index) {
+                let hydrogen = self.molecule.add_node(H);
+                self.molecule.add_edge(index, hydrogen, 1);
+                self.coordinates.push(self.coordinates[index.index()]);
+            }
+        }
+        if self
+            .coordinates
+            .iter()
+            .flatten()
+            .all(|&coordinate| coordinate == 0.0)
+        {
+            self.coordinates.clear();
+        }
+        Ok((self.molecule, self.coordinates))
+    }
+}
+
+/// CAS registry number with its hyphens
+fn cas_number(cas: u64) -> String {
+    let digits = cas.to_string();
+    match digits.len() {
+        length if length > 3 => format!(
+            "{}-{}-{}",
+            &digits[..length - 3],
+            &digits[length - 3..length - 1],
+            &digits[length - 1..],
+        ),
+        _ => digits,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ETHANOL: &str = "\
+Ethanol
+  msp
+
+  3  2  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    2.0000    1.0000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0  0  0  0
+  2  3  1  0  0  0  0
+M  END
+> <CAS>
+64-17-5
+
+> <SOURCE>
+colleague
+
+$$$$
+";
+
+    #[test]
+    fn read() {
+        let records = Record::read(ETHANOL).unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.parsed.name, "Ethanol");
+        assert_eq!(record.parsed.cas, Some(64175));
+        assert_eq!(record.parsed.formula, "C2H6O");
+        assert_eq!(record.molecule.node_count(), 9);
+        assert_eq!(
+            record.fields,
+            [("SOURCE".to_owned(), "colleague".to_owned())]
+        );
+        let parsed = Parsed {
+            name: "ethanol".to_owned(),
+            ..Default::default()
+        };
+        assert!(record.matches(&parsed));
+        assert!(Record::read("Broken\n\n\n  1  0\nM  END\n").is_err());
+        // The name after the first `<`, a malformed data header skipped
+        let record = &Record::read(&ETHANOL.replace("> <SOURCE>", "> a>b <SOURCE>")).unwrap()[0];
+        assert_eq!(record.fields[0].0, "SOURCE");
+        let record = &Record::read(&ETHANOL.replace("> <SOURCE>", "> a>b <SOURCE")).unwrap()[0];
+        assert!(record.fields.is_empty());
+    }
+
+    #[test]
+    fn write() {
+        let record = &Record::read(ETHANOL).unwrap()[0];
+        for version in [Version::V2000, Version::V3000] {
+            let written = record.write(version);
+            assert!(written.contains("> <CAS>\n64-17-5\n"));
+            let read = &Record::read(&written).unwrap()[0];
+            assert_eq!(read.parsed.cas, record.parsed.cas);
+            assert_eq!(read.molecule.node_count(), 9);
+            assert_eq!(read.molecule.smiles(), "CCO");
+            assert_eq!(read.coordinates, record.coordinates);
+        }
+        assert_eq!(record.coordinates[2], [2.0, 1.0, 0.0]);
+        // The added hydrogens at their heavy atom
+        assert_eq!(record.coordinates[8], [2.0, 1.0, 0.0]);
+        let benzene = &Record::read(
+            &("\n\n\n  6  6  0  0  0  0  0  0  0  0999 V2000\n".to_owned()
+                + &"    0.0000    0.0000    0.0000 C   0  0\n".repeat(6)
+                + "  1  2  4\n  2  3  4\n  3  4  4\n  4  5  4\n  5  6  4\n  6  1  4\nM  END\n"),
+        )
+        .unwrap()[0];
+        assert_eq!(benzene.molecule.smiles(), "C1=CC=CC=C1");
+        // Without coordinates: laid out
+        assert!(benzene.coordinates.is_empty());
+        let written = benzene.write(Version::V2000);
+        let read = &Record::read(&written).unwrap()[0];
+        assert_eq!(read.coordinates.len(), 12);
+        // The charges
+        let acetate = "CC(=O)[O-]".parse::<Molecule>().unwrap();
+        for version in [Version::V2000, Version::V3000] {
+            let read = &Record::read(&acetate.molfile("", version)).unwrap()[0];
+            assert_eq!(read.molecule.formula().to_string(), "C2H3O2");
+            assert_eq!(read.molecule.charge(), Some(-1));
+        }
+    }
+}
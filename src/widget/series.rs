@@ -0,0 +1,278 @@
+//! Homologous series: straight-chain and branched alkanes, alkenes and
+//! polyenes, and fatty acids with their derivative head groups.
+//!
+//! The chains are written as SMILES (the double bond geometry as `/` `\` bond
+//! directions) and parsed into molecules, which drop the geometry. Branches
+//! and double bonds out of the chain are ignored.
+
+use super::{canonical::dedup, molecule::Molecule};
+use crate::{
+    formula::Formula,
+    lipid::{Derivative, FattyAcid, Geometry, Modification, Position},
+};
+use anyhow::{bail, ensure, Context, Result};
+use itertools::Itertools;
+
+/// Maximum number of the isomers generated (or scored) at once
+pub const MAX_ISOMERS: f64 = 1000.0;
+
+/// Straight-chain alkane (CnH2n+2)
+pub fn alkane(carbons: usize) -> Result<Molecule> {
+    branched_alkane(carbons, &[])
+}
+
+/// Alkane with alkyl branches (the carbon of the main chain and the length
+/// of the branch)
+pub fn branched_alkane(carbons: usize, branches: &[(usize, usize)]) -> Result<Molecule> {
+    molecule(&chain("C", carbons, branches, &[], None))
+}
+
+/// Straight-chain alkene or polyene with double bonds at the positions
+pub fn alkene(carbons: usize, positions: &[Position]) -> Result<Molecule> {
+    molecule(&alkene_smiles(carbons, positions))
+}
+
+/// Alkenes or polyenes of all the positions of the non-cumulated double
+/// bonds, without the duplicates by symmetry
+pub fn alkene_isomers(carbons: usize, bonds: usize) -> Result<Vec<Molecule>> {
+    let isomers = (1..carbons)
+        .combinations(bonds)
+        .filter(|positions| positions.windows(2).all(|pair| pair[0] + 1 < pair[1]))
+        .map(|positions| {
+            let positions = positions
+                .into_iter()
+                .map(|carbon| Position {
+                    carbon,
+                    geometry: None,
+                })
+                .collect::<Vec<_>>();
+            alkene(carbons, &positions)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(dedup(isomers))
+}
+
+/// Straight-chain isomers with the double bonds (the alkane if none)
+pub fn isomers(carbons: usize, bonds: usize) -> Result<Vec<Molecule>> {
+    match bonds {
+        0 => Ok(vec![alkane(carbons)?]),
+        _ => alkene_isomers(carbons, bonds),
+    }
+}
+
+/// Carbons and double bonds of an acyclic hydrocarbon formula (CnH2n+2-2k)
+pub fn hydrocarbon(formula: &Formula) -> Result<(usize, usize)> {
+    let carbons = formula.count("C");
+    let hydrogens = formula.count("H");
+    ensure!(
+        carbons > 0 && carbons + hydrogens == formula.atoms.values().sum::<usize>(),
+        "{formula} isn't a hydrocarbon",
+    );
+    let bonds = (carbons + 1).saturating_sub(hydrogens / 2);
+    ensure!(
+        2 * bonds + hydrogens == 2 * carbons + 2,
+        "{formula} isn't an acyclic hydrocarbon",
+    );
+    // Non-adjacent positions among the carbons - 1 bonds of the chain
+    let slots = carbons.saturating_sub(bonds);
+    let count = (0..bonds).fold(1.0, |count, index| {
+        count * slots.saturating_sub(index) as f64 / (index + 1) as f64
+    });
+    ensure!(count <= MAX_ISOMERS, "{formula} has too many isomers");
+    Ok((carbons, bonds))
+}
+
+/// SMILES of a straight-chain alkene or polyene (with the geometry)
+pub fn alkene_smiles(carbons: usize, positions: &[Position]) -> String {
+    chain("C", carbons, &[], positions, None)
+}
+
+/// Fatty acid (free if no derivative)
+pub fn fatty_acid(fatty_acid: &FattyAcid, derivative: Option<Derivative>) -> Result<Molecule> {
+    molecule(&fatty_acid_smiles(fatty_acid, derivative)?)
+}
+
+/// SMILES of a fatty acid (with the geometry), from its head group
+pub fn fatty_acid_smiles(fatty_acid: &FattyAcid, derivative: Option<Derivative>) -> Result<String> {
+    if fatty_acid.positions.len() != fatty_acid.bonds {
+        bail!("double bond positions of {fatty_acid} are unknown");
+    }
+    // The carboxyl carbon (C1)
+    let head = match derivative {
+        None => "OC(=O)",
+        Some(Derivative::MethylEster) => "COC(=O)",
+        // Pyridin-3-ylmethyl ester
+        Some(Derivative::Picolinyl) => "n1cccc(c1)COC(=O)",
+        // 4,4-Dimethyloxazoline
+        Some(Derivative::Dmox) => "CC1(C)COC(=N1)",
+        Some(Derivative::Pyrrolidide) => "C1CCN(C1)C(=O)",
+    };
+    let chain_length = fatty_acid.main_chain();
+    let (branches, ring) = match fatty_acid.modification {
+        None => (vec![], None),
+        Some(Modification::Iso) => (vec![(chain_length - 1, 1)], None),
+        Some(Modification::Anteiso) => (vec![(chain_length - 2, 1)], None),
+        Some(Modification::Cyclo(Some(position))) if (1..chain_length).contains(&position) => {
+            (vec![], Some(position))
+        }
+        Some(Modification::Cyclo(Some(position))) => {
+            bail!("ring position {position} of {fatty_acid} is out of the chain")
+        }
+        Some(Modification::Cyclo(None)) => bail!("ring position of {fatty_acid} is unknown"),
+    };
+    Ok(chain(
+        head,
+        chain_length,
+        &branches,
+        &fatty_acid.positions,
+        ring,
+    ))
+}
+
+/// SMILES of a carbon chain from C1 (written as the `head`) with the
+/// branches, the double bonds and a cyclopropane ring bridging C(k) and
+/// C(k+1)
+fn chain(
+    head: &str,
+    carbons: usize,
+    branches: &[(usize, usize)],
+    positions: &[Position],
+    ring: Option<usize>,
+) -> String {
+    // Bond symbols before the carbons (`=`, `/` or `\`)
+    let mut bonds = vec![None; carbons + 2];
+    let mut positions = positions
+        .iter()
+        .filter(|position| (1..carbons).contains(&position.carbon))
+        .collect::<Vec<_>>();
+    positions.sort_by_key(|position| position.carbon);
+    for position in &positions {
+        bonds[position.carbon + 1] = Some('=');
+    }
+    for position in positions {
+        let carbon = position.carbon;
+        let Some(geometry) = position.geometry else {
+            continue;
+        };
+        if carbon < 2 || carbon + 2 > carbons {
+            continue;
+        }
+        let before = match bonds[carbon] {
+            Some(direction @ ('/' | '\\')) => direction,
+            Some(_) => continue,
+            None => '/',
+        };
+        let after = match (geometry, before) {
+            (Geometry::Trans, direction) => direction,
+            (Geometry::Cis, '/') => '\\',
+            (Geometry::Cis, _) => '/',
+        };
+        bonds[carbon] = Some(before);
+        if bonds[carbon + 2].is_none() {
+            bonds[carbon + 2] = Some(after);
+        }
+    }
+    let mut smiles = String::new();
+    for (carbon, &bond) in bonds.iter().enumerate().take(carbons + 1).skip(1) {
+        match carbon {
+            1 => smiles.push_str(head),
+            _ => {
+                smiles.extend(bond);
+                smiles.push('C');
+            }
+        }
+        match ring {
+            Some(position) if position == carbon && carbon < carbons => smiles.push('9'),
+            Some(position) if position + 1 == carbon => smiles.push_str("(C9)"),
+            _ => {}
+        }
+        for &(_, length) in branches.iter().filter(|&&(branch, _)| branch == carbon) {
+            smiles.push('(');
+            smiles.push_str(&"C".repeat(length));
+            smiles.push(')');
+        }
+    }
+    smiles
+}
+
+fn molecule(smiles: &str) -> Result<Molecule> {
+    smiles
+        .parse()
+        .with_context(|| format!("generated SMILES {smiles}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn formula(molecule: &Molecule) -> String {
+        molecule.formula().to_string()
+    }
+
+    #[test]
+    fn hydrocarbons() {
+        assert_eq!(formula(&alkane(1).unwrap()), "CH4");
+        assert_eq!(formula(&alkane(10).unwrap()), "C10H22");
+        let isooctane = branched_alkane(5, &[(2, 1), (2, 1), (4, 1)]).unwrap();
+        assert_eq!(formula(&isooctane), "C8H18");
+        assert_eq!(isooctane.smiles(), "CC(C)CC(C)(C)C");
+        let positions = [
+            Position {
+                carbon: 2,
+                geometry: Some(Geometry::Trans),
+            },
+            Position {
+                carbon: 4,
+                geometry: Some(Geometry::Cis),
+            },
+        ];
+        assert_eq!(alkene_smiles(6, &positions), "C/C=C/C=C\\C");
+        let hexadiene = alkene(6, &positions).unwrap();
+        assert_eq!(formula(&hexadiene), "C6H10");
+        assert!(hexadiene.violations().is_empty());
+        assert_eq!(alkene_isomers(6, 1).unwrap().len(), 3);
+        assert_eq!(alkene_isomers(6, 2).unwrap().len(), 4);
+        let hydrocarbon = |formula: &str| hydrocarbon(&formula.parse().unwrap());
+        assert_eq!(hydrocarbon("C6H12").unwrap(), (6, 1));
+        assert_eq!(hydrocarbon("C5H12").unwrap(), (5, 0));
+        assert!(hydrocarbon("C6H6O").is_err());
+        assert!(hydrocarbon("C6H16").is_err());
+        assert!(hydrocarbon("C40H70").is_err());
+        assert_eq!(formula(&isomers(5, 0).unwrap()[0]), "C5H12");
+        assert_eq!(isomers(6, 1).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn fatty_acids() {
+        let linoleic = "18:2(9Z,12Z)".parse::<FattyAcid>().unwrap();
+        let smiles = fatty_acid_smiles(&linoleic, None).unwrap();
+        assert_eq!(smiles, "OC(=O)CCCCCCC/C=C\\C/C=C\\CCCCC");
+        let formulas = [
+            (None, "C18H32O2"),
+            (Some(Derivative::MethylEster), "C19H34O2"),
+            (Some(Derivative::Picolinyl), "C24H37NO2"),
+            (Some(Derivative::Dmox), "C22H39NO"),
+            (Some(Derivative::Pyrrolidide), "C22H39NO"),
+        ];
+        for (derivative, expected) in formulas {
+            let molecule = fatty_acid(&linoleic, derivative).unwrap();
+            assert_eq!(formula(&molecule), expected);
+            let mass =
+                linoleic.mass().unwrap() + derivative.map_or(0, |derivative| derivative.shift());
+            assert_eq!(molecule.formula().nominal(), mass);
+        }
+        for (name, expected) in [("i-15:0", "C15H30O2"), ("cy-19:0(11)", "C19H36O2")] {
+            let fatty_acid = fatty_acid(&name.parse().unwrap(), None).unwrap();
+            assert_eq!(formula(&fatty_acid), expected);
+        }
+        assert!(fatty_acid(&"18:1".parse().unwrap(), None).is_err());
+        // The ring out of the chain
+        for position in [0, 18, 30] {
+            let cyclo = FattyAcid {
+                modification: Some(Modification::Cyclo(Some(position))),
+                .."cy-19:0(11)".parse().unwrap()
+            };
+            assert!(fatty_acid(&cyclo, None).is_err());
+        }
+    }
+}
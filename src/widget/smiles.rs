@@ -0,0 +1,576 @@
+//! SMILES: atoms (`C`, `Cl`, `[NH4+]`), bonds (`-`, `=`, `#`, `$`, `:`),
+//! branches (`CC(C)C`), ring closures (`C1CCCCC1`, `%10`), dots, aromatic
+//! atoms (`c1ccccc1`, kekulized on parsing) and implicit hydrogens (added as
+//! hydrogen atoms). Charges, isotopes, chirality and the `/` `\` bond
+//! directions are accepted but dropped, since an atom is only its element.
+
+use super::{
+    atom::{Element, H},
+    molecule::Molecule,
+};
+use anyhow::{bail, Error, Result};
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, digit1, one_of, satisfy},
+    combinator::{all_consuming, map, map_opt, map_res, opt, recognize, value},
+    multi::many0,
+    sequence::{delimited, pair, preceded, tuple},
+    IResult,
+};
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    str::{self, FromStr},
+};
+
+/// Elements written without brackets (with implicit hydrogens)
+const ORGANIC: [&str; 10] = ["B", "C", "N", "O", "P", "S", "F", "Cl", "Br", "I"];
+
+pub fn parse(input: &str) -> Result<Vec<Token>, nom::Err<nom::error::Error<&str>>> {
+    let (_, tokens) = all_consuming(many0(token))(input)?;
+    Ok(tokens)
+}
+
+fn token(input: &str) -> IResult<&str, Token> {
+    alt((
+        map(atom, Token::Atom),
+        map(ring, Token::Ring),
+        map(bond, Token::Bond),
+        value(Token::Open, char('(')),
+        value(Token::Close, char(')')),
+        value(Token::Dot, char('.')),
+    ))(input)
+}
+
+fn atom(input: &str) -> IResult<&str, Atom> {
+    alt((bracket, organic))(input)
+}
+
+fn organic(input: &str) -> IResult<&str, Atom> {
+    alt((
+        map_opt(
+            alt((tag("Cl"), tag("Br"), recognize(one_of("BCNOPSFI")))),
+            |symbol| Atom::new(symbol, false, None),
+        ),
+        map_opt(recognize(one_of("bcnops")), |symbol: &str| {
+            Atom::new(&symbol.to_uppercase(), true, None)
+        }),
+    ))(input)
+}
+
+/// `[` isotope? symbol chirality? hydrogens? charge? class? `]`
+fn bracket(input: &str) -> IResult<&str, Atom> {
+    map_opt(
+        delimited(
+            char('['),
+            tuple((
+                opt(digit1),
+                alt((
+                    map(
+                        recognize(pair(
+                            satisfy(|c| c.is_ascii_uppercase()),
+                            opt(satisfy(|c| c.is_ascii_lowercase())),
+                        )),
+                        |symbol: &str| (symbol.to_owned(), false),
+                    ),
+                    map(
+                        alt((tag("se"), tag("as"), recognize(one_of("bcnops")))),
+                        |symbol: &str| (symbol[..1].to_uppercase() + &symbol[1..], true),
+                    ),
+                )),
+                many0(char('@')),
+                opt(preceded(char('H'), opt(number))),
+                opt(pair(one_of("+-"), opt(number))),
+                opt(preceded(char(':'), digit1)),
+            )),
+            char(']'),
+        ),
+        |(_, (symbol, aromatic), _, hydrogens, ..)| {
+            Atom::new(
+                &symbol,
+                aromatic,
+                Some(hydrogens.map_or(0, |count| count.unwrap_or(1))),
+            )
+        },
+    )(input)
+}
+
+fn bond(input: &str) -> IResult<&str, Bond> {
+    alt((
+        value(Bond::Order(1), one_of("-/\\")),
+        value(Bond::Order(2), char('=')),
+        value(Bond::Order(3), char('#')),
+        value(Bond::Order(4), char('$')),
+        value(Bond::Aromatic, char(':')),
+    ))(input)
+}
+
+/// Ring closure (with its bond)
+fn ring(input: &str) -> IResult<&str, (Option<Bond>, usize)> {
+    pair(
+        opt(bond),
+        alt((
+            map_res(recognize(satisfy(|c| c.is_ascii_digit())), str::parse),
+            preceded(
+                char('%'),
+                map_res(
+                    recognize(pair(
+                        satisfy(|c| c.is_ascii_digit()),
+                        satisfy(|c| c.is_ascii_digit()),
+                    )),
+                    str::parse,
+                ),
+            ),
+        )),
+    )(input)
+}
+
+fn number(input: &str) -> IResult<&str, usize> {
+    map_res(digit1, str::parse)(input)
+}
+
+/// Token
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Token {
+    Atom(Atom),
+    Bond(Bond),
+    Ring((Option<Bond>, usize)),
+    Open,
+    Close,
+    Dot,
+}
+
+/// Atom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Atom {
+    pub element: &'static Element,
+    pub aromatic: bool,
+    /// Hydrogen count of a bracket atom (`None` if implicit)
+    pub hydrogens: Option<usize>,
+}
+
+impl Atom {
+    fn new(symbol: &str, aromatic: bool, hydrogens: Option<usize>) -> Option<Self> {
+        Some(Self {
+            element: Element::find(symbol)?,
+            aromatic,
+            hydrogens,
+        })
+    }
+}
+
+/// Bond
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bond {
+    Order(u8),
+    Aromatic,
+}
+
+impl FromStr for Molecule {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let tokens = parse(value.trim()).map_err(|error| error.to_owned())?;
+        let mut builder = Builder::default();
+        for token in tokens {
+            builder.push(token)?;
+        }
+        builder.build()
+    }
+}
+
+/// Builder of a molecule from the tokens
+#[derive(Default)]
+struct Builder {
+    molecule: Molecule,
+    atoms: Vec<Atom>,
+    aromatic: Vec<(NodeIndex, NodeIndex)>,
+    previous: Option<NodeIndex>,
+    bond: Option<Bond>,
+    branches: Vec<Option<NodeIndex>>,
+    rings: HashMap<usize, (NodeIndex, Option<Bond>)>,
+}
+
+impl Builder {
+    fn push(&mut self, token: Token) -> Result<()> {
+        match token {
+            Token::Atom(atom) => {
+                let index = self.molecule.add_node(atom.element);
+                self.atoms.push(atom);
+                if let Some(previous) = self.previous {
+                    let bond = self.bond.take();
+                    self.connect(previous, index, bond);
+                }
+                self.previous = Some(index);
+            }
+            Token::Bond(bond) => {
+                if self.previous.is_none() || self.bond.replace(bond).is_some() {
+                    bail!("unexpected bond");
+                }
+            }
+            Token::Ring((bond, number)) => {
+                let Some(previous) = self.previous else {
+                    bail!("ring closure {number} without an atom");
+                };
+                match self.rings.remove(&number) {
+                    Some((opening, other)) => {
+                        if opening == previous {
+                            bail!("ring closure {number} on a single atom");
+                        }
+                        self.connect(opening, previous, bond.or(other));
+                    }
+                    None => {
+                        self.rings.insert(number, (previous, bond));
+                    }
+                }
+            }
+            Token::Open => {
+                if self.previous.is_none() {
+                    bail!("branch without an atom");
+                }
+                self.branches.push(self.previous);
+            }
+            Token::Close => match self.branches.pop() {
+                Some(previous) => self.previous = previous,
+                None => bail!("unbalanced branch"),
+            },
+            Token::Dot => self.previous = None,
+        }
+        Ok(())
+    }
+
+    fn connect(&mut self, lhs: NodeIndex, rhs: NodeIndex, bond: Option<Bond>) {
+        let aromatic = self.atoms[lhs.index()].aromatic && self.atoms[rhs.index()].aromatic;
+        match bond {
+            Some(Bond::Order(order)) => {
+                self.molecule.add_edge(lhs, rhs, order);
+            }
+            Some(Bond::Aromatic) => {
+                self.molecule.add_edge(lhs, rhs, 1);
+                self.aromatic.push((lhs, rhs));
+            }
+            None if aromatic => {
+                self.molecule.add_edge(lhs, rhs, 1);
+                self.aromatic.push((lhs, rhs));
+            }
+            None => {
+                self.molecule.add_edge(lhs, rhs, 1);
+            }
+        }
+    }
+
+    fn build(mut self) -> Result<Molecule> {
+        if let Some(number) = self.rings.keys().next() {
+            bail!("unclosed ring {number}");
+        }
+        if !self.branches.is_empty() {
+            bail!("unclosed branch");
+        }
+        let hydrogens = self
+            .atoms
+            .iter()
+            .map(|atom| atom.hydrogens.unwrap_or_default())
+            .collect::<Vec<_>>();
+        if !kekulize(&mut self.molecule, &self.aromatic, &hydrogens) {
+            bail!("aromatic system can't be kekulized");
+        }
+        let heavy = self.molecule.node_indices().collect::<Vec<_>>();
+        for index in heavy {
+            let count = match self.atoms[index.index()].hydrogens {
+                Some(count) => count,
+                None => self.molecule.implicit_hydrogens(index),
+            };
+            for _ in 0..count {
+                let hydrogen = self.molecule.add_node(H);
+                self.molecule.add_edge(index, hydrogen, 1);
+            }
+        }
+        Ok(self.molecule)
+    }
+}
+
+impl Molecule {
+    /// Canonical SMILES (kekulized, with implicit hydrogens)
+    pub fn smiles(&self) -> String {
+        self.write(&self.canonical_ranks(), &HashSet::new())
+    }
+
+    /// SMILES from the lowest ranks (the `aromatic` bonds written `:`)
+    pub(super) fn write(&self, ranks: &[usize], aromatic: &HashSet<EdgeIndex>) -> String {
+        let mut atoms = self
+            .node_indices()
+            .filter(|&index| {
+                self[index] != H || self.neighbors(index).all(|index| self[index] == H)
+            })
+            .collect::<Vec<_>>();
+        atoms.sort_by_key(|index| ranks[index.index()]);
+        let mut writer = Writer {
+            molecule: self,
+            ranks,
+            aromatic,
+            visited: vec![false; self.node_count()],
+            closures: Vec::new(),
+        };
+        let mut components = Vec::new();
+        for &atom in &atoms {
+            if writer.visited[atom.index()] {
+                continue;
+            }
+            writer.search(atom, None);
+            components.push(writer.write(atom, None, &mut Vec::new()));
+        }
+        components.join(".")
+    }
+
+    pub(super) fn heavy(&self, index: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.neighbors(index)
+            .filter(move |&neighbor| self[neighbor] != H || self[index] == H)
+    }
+}
+
+/// Writer of the SMILES of a molecule
+struct Writer<'a> {
+    molecule: &'a Molecule,
+    ranks: &'a [usize],
+    aromatic: &'a HashSet<EdgeIndex>,
+    visited: Vec<bool>,
+    /// Ring closure bonds (opening and closing atoms)
+    closures: Vec<(NodeIndex, NodeIndex)>,
+}
+
+impl Writer<'_> {
+    /// Depth-first search finding the ring closures
+    fn search(&mut self, atom: NodeIndex, parent: Option<NodeIndex>) {
+        self.visited[atom.index()] = true;
+        for neighbor in self.neighbors(atom, parent) {
+            if self.visited[neighbor.index()] {
+                if !self.closures.contains(&(atom, neighbor)) {
+                    self.closures.push((neighbor, atom));
+                }
+            } else {
+                self.search(neighbor, Some(atom));
+            }
+        }
+    }
+
+    fn write(
+        &self,
+        atom: NodeIndex,
+        parent: Option<NodeIndex>,
+        digits: &mut Vec<Option<(NodeIndex, NodeIndex)>>,
+    ) -> String {
+        let mut smiles = self.symbol(atom);
+        for &(opening, closing) in &self.closures {
+            if opening == atom {
+                let digit = match digits.iter().position(Option::is_none) {
+                    Some(digit) => digit,
+                    None => {
+                        digits.push(None);
+                        digits.len() - 1
+                    }
+                };
+                digits[digit] = Some((opening, closing));
+                smiles.push_str(&ring_digit(digit + 1));
+            } else if closing == atom {
+                if let Some(digit) = digits
+                    .iter()
+                    .position(|&pair| pair == Some((opening, closing)))
+                {
+                    digits[digit] = None;
+                    smiles.push_str(&self.bond_symbol(opening, closing));
+                    smiles.push_str(&ring_digit(digit + 1));
+                }
+            }
+        }
+        let children = self
+            .neighbors(atom, parent)
+            .into_iter()
+            .filter(|&child| {
+                !self.closures.contains(&(atom, child)) && !self.closures.contains(&(child, atom))
+            })
+            .collect::<Vec<_>>();
+        for (index, &child) in children.iter().enumerate() {
+            let branch = self.bond_symbol(atom, child) + &self.write(child, Some(atom), digits);
+            if index + 1 < children.len() {
+                smiles.push_str(&format!("({branch})"));
+            } else {
+                smiles.push_str(&branch);
+            }
+        }
+        smiles
+    }
+
+    fn bond_symbol(&self, lhs: NodeIndex, rhs: NodeIndex) -> String {
+        let molecule = self.molecule;
+        match molecule.find_edge(lhs, rhs) {
+            Some(edge) if self.aromatic.contains(&edge) => ":".to_owned(),
+            _ => bond_symbol(order(molecule, lhs, rhs)),
+        }
+    }
+
+    /// Heavy neighbors (except the parent) from the lowest rank
+    fn neighbors(&self, atom: NodeIndex, parent: Option<NodeIndex>) -> Vec<NodeIndex> {
+        let mut neighbors = self
+            .molecule
+            .heavy(atom)
+            .filter(|&neighbor| Some(neighbor) != parent)
+            .collect::<Vec<_>>();
+        neighbors.sort_by_key(|neighbor| self.ranks[neighbor.index()]);
+        neighbors.dedup();
+        neighbors
+    }
+
+    fn symbol(&self, atom: NodeIndex) -> String {
+        let molecule = self.molecule;
+        let element = molecule[atom];
+        let hydrogens = molecule
+            .neighbors(atom)
+            .filter(|&neighbor| molecule[neighbor] == H)
+            .count();
+        if element != H && ORGANIC.contains(&element.symbol) {
+            let bonds = molecule.bonds(atom) - hydrogens;
+            if element.implicit_hydrogens(bonds) == hydrogens {
+                return element.symbol.to_owned();
+            }
+        }
+        match hydrogens {
+            _ if element == H => "[H]".to_owned(),
+            0 => format!("[{element}]"),
+            1 => format!("[{element}H]"),
+            hydrogens => format!("[{element}H{hydrogens}]"),
+        }
+    }
+}
+
+/// Turns the aromatic bonds into alternating single and double bonds (the
+/// explicit `hydrogens` of the atoms count into their valences)
+pub(super) fn kekulize(
+    molecule: &mut Molecule,
+    aromatic: &[(NodeIndex, NodeIndex)],
+    hydrogens: &[usize],
+) -> bool {
+    let needs = aromatic
+        .iter()
+        .flat_map(|&(lhs, rhs)| [lhs, rhs])
+        .filter(|&index| {
+            let used = molecule.bonds(index) + hydrogens.get(index.index()).unwrap_or(&0);
+            molecule[index]
+                .valences
+                .first()
+                .is_some_and(|&valence| used < valence as usize)
+        })
+        .collect::<BTreeSet<_>>();
+    let mut doubles = Vec::new();
+    if !matching(aromatic, needs, &mut doubles) {
+        return false;
+    }
+    for (lhs, rhs) in doubles {
+        if let Some(edge) = molecule.find_edge(lhs, rhs) {
+            molecule[edge] = 2;
+        }
+    }
+    true
+}
+
+/// Perfect matching of the atoms needing a double bond along the aromatic
+/// bonds (backtracking)
+fn matching(
+    aromatic: &[(NodeIndex, NodeIndex)],
+    mut needs: BTreeSet<NodeIndex>,
+    doubles: &mut Vec<(NodeIndex, NodeIndex)>,
+) -> bool {
+    let Some(first) = needs.pop_first() else {
+        return true;
+    };
+    let partners = aromatic
+        .iter()
+        .filter_map(|&(lhs, rhs)| match first {
+            _ if lhs == first => Some(rhs),
+            _ if rhs == first => Some(lhs),
+            _ => None,
+        })
+        .filter(|partner| needs.contains(partner))
+        .collect::<Vec<_>>();
+    for partner in partners {
+        let mut rest = needs.clone();
+        rest.remove(&partner);
+        doubles.push((first, partner));
+        if matching(aromatic, rest, doubles) {
+            return true;
+        }
+        doubles.pop();
+    }
+    false
+}
+
+pub(super) fn order(molecule: &Molecule, lhs: NodeIndex, rhs: NodeIndex) -> u8 {
+    molecule
+        .find_edge(lhs, rhs)
+        .map_or(1, |edge| molecule[edge])
+}
+
+fn bond_symbol(order: u8) -> String {
+    match order {
+        2 => "=",
+        3 => "#",
+        4 => "$",
+        _ => "",
+    }
+    .to_owned()
+}
+
+fn ring_digit(digit: usize) -> String {
+    if digit < 10 {
+        digit.to_string()
+    } else {
+        format!("%{digit}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hydrogens(molecule: &Molecule) -> usize {
+        molecule
+            .node_indices()
+            .filter(|&index| molecule[index] == H)
+            .count()
+    }
+
+    #[test]
+    fn parse() {
+        let molecule = "CC(C)C".parse::<Molecule>().unwrap();
+        assert_eq!((molecule.node_count(), hydrogens(&molecule)), (14, 10));
+        let molecule = "c1ccccc1".parse::<Molecule>().unwrap();
+        assert_eq!(hydrogens(&molecule), 6);
+        assert_eq!(
+            molecule.edge_weights().filter(|&&order| order == 2).count(),
+            3
+        );
+        let molecule = "c1cc[nH]c1".parse::<Molecule>().unwrap();
+        assert_eq!(hydrogens(&molecule), 5);
+        assert!("C1CC%10CC1.[NH4+]".parse::<Molecule>().is_err());
+        assert!("C1CCC".parse::<Molecule>().is_err());
+        assert!("CC)C".parse::<Molecule>().is_err());
+        assert!("c1cccc1".parse::<Molecule>().is_err());
+    }
+
+    #[test]
+    fn write() {
+        for (smiles, canonical) in [
+            ("CC(C)C", "CC(C)C"),
+            ("OC(=O)CCl", "O=C(O)CCl"),
+            ("c1ccccc1", "C1=CC=CC=C1"),
+            ("C#N", "C#N"),
+            ("[NH4+].[Cl-]", "[NH4].[Cl]"),
+        ] {
+            let molecule = smiles.parse::<Molecule>().unwrap();
+            assert_eq!(molecule.smiles(), canonical);
+            let written = molecule.smiles().parse::<Molecule>().unwrap();
+            assert_eq!(written.node_count(), molecule.node_count());
+            assert!(written.is_isomorphic(&molecule));
+        }
+    }
+}
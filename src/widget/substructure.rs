@@ -0,0 +1,395 @@
+//! Substructure search: all the mappings of the query atoms to the atoms of
+//! a molecule (VF2-like backtracking, the query atoms taken in breadth-first
+//! order and their candidates among the neighbors of the mapped ones).
+//!
+//! Atoms match on their element identity, bonds on their order. Aromatic
+//! bonds are perceived on the kekulized rings (of five to seven atoms with
+//! 4n+2 π electrons) and match only aromatic query bonds.
+
+use super::{
+    atom::{Element, H},
+    molecule::Molecule,
+    molfile::Record,
+};
+use petgraph::{
+    graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
+};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet, VecDeque};
+
+/// Substructure query
+pub type Query = Molecule<AtomQuery, BondQuery>;
+
+/// Query atom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AtomQuery {
+    Any,
+    Element(&'static Element),
+    /// Atom of an aromatic ring
+    Aromatic,
+}
+
+/// Query bond
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BondQuery {
+    Any,
+    /// Non-aromatic bond of the order
+    Order(u8),
+    Aromatic,
+}
+
+/// Query of the heavy atoms of the molecule (its aromatic bonds as aromatic
+/// query bonds)
+impl From<&Molecule> for Query {
+    fn from(molecule: &Molecule) -> Self {
+        let aromatic = molecule.aromatic_bonds();
+        let mut query = Query::default();
+        let mut indices = HashMap::new();
+        for index in molecule.node_indices() {
+            if molecule[index] != H {
+                let atom = query.add_node(AtomQuery::Element(molecule[index]));
+                indices.insert(index, atom);
+            }
+        }
+        for edge in molecule.edge_references() {
+            let (Some(&source), Some(&target)) =
+                (indices.get(&edge.source()), indices.get(&edge.target()))
+            else {
+                continue;
+            };
+            let bond = match aromatic.contains(&edge.id()) {
+                true => BondQuery::Aromatic,
+                false => BondQuery::Order(*edge.weight()),
+            };
+            query.add_edge(source, target, bond);
+        }
+        query
+    }
+}
+
+impl Query {
+    /// Query matching any element (the atoms of its aromatic bonds any
+    /// aromatic atom)
+    pub fn any_element(mut self) -> Self {
+        for atom in self.node_indices().collect::<Vec<_>>() {
+            let aromatic = self
+                .edges(atom)
+                .any(|edge| *edge.weight() == BondQuery::Aromatic);
+            self[atom] = match aromatic {
+                true => AtomQuery::Aromatic,
+                false => AtomQuery::Any,
+            };
+        }
+        self
+    }
+
+    /// Query matching any bond (of any order, aromatic or not)
+    pub fn any_bond(mut self) -> Self {
+        for bond in self.edge_weights_mut() {
+            *bond = BondQuery::Any;
+        }
+        self
+    }
+}
+
+impl Molecule {
+    /// All the mappings of the query atoms (by their index) to the atoms
+    pub fn substructures(&self, query: &Query) -> Vec<Vec<NodeIndex>> {
+        let mut matcher = Matcher::new(self, query, false);
+        matcher.search(0);
+        matcher.mappings
+    }
+
+    /// The query is a substructure
+    pub fn contains(&self, query: &Query) -> bool {
+        let mut matcher = Matcher::new(self, query, true);
+        matcher.search(0);
+        !matcher.mappings.is_empty()
+    }
+
+    /// Smallest rings through each bond
+    pub fn rings(&self) -> Vec<Vec<NodeIndex>> {
+        let mut rings = Vec::new();
+        let mut seen = HashSet::new();
+        for edge in self.edge_references() {
+            let Some(ring) = self.shortest_path(edge.source(), edge.target(), edge.id()) else {
+                continue;
+            };
+            if seen.insert(ring.iter().copied().collect::<BTreeSet<_>>()) {
+                rings.push(ring);
+            }
+        }
+        rings
+    }
+
+    /// Bonds of the aromatic rings (fused rings are aromatic with the double
+    /// bonds shared with the aromatic ones)
+    pub fn aromatic_bonds(&self) -> HashSet<EdgeIndex> {
+        let rings = self
+            .rings()
+            .into_iter()
+            .filter(|ring| (5..=7).contains(&ring.len()))
+            .collect::<Vec<_>>();
+        let mut aromatic = HashSet::new();
+        loop {
+            let mut changed = false;
+            for ring in &rings {
+                let bonds = ring
+                    .iter()
+                    .zip(ring.iter().cycle().skip(1))
+                    .filter_map(|(&lhs, &rhs)| self.find_edge(lhs, rhs))
+                    .collect::<Vec<_>>();
+                if bonds.iter().all(|bond| aromatic.contains(bond)) {
+                    continue;
+                }
+                if self.is_aromatic(ring, &aromatic) {
+                    aromatic.extend(bonds);
+                    changed = true;
+                }
+            }
+            if !changed {
+                return aromatic;
+            }
+        }
+    }
+
+    /// Hückel rule on the π electrons of the ring
+    fn is_aromatic(&self, ring: &[NodeIndex], aromatic: &HashSet<EdgeIndex>) -> bool {
+        let mut electrons = 0;
+        for &atom in ring {
+            let double = self.edges(atom).find(|edge| *edge.weight() == 2);
+            // Lone pair of a saturated heteroatom (pyrrole, furan)
+            let lone_pair = self[atom]
+                .valence_electrons()
+                .is_some_and(|electrons| electrons > 4)
+                && self.formal_charge(atom) == Some(0);
+            electrons += match double {
+                Some(edge) if ring.contains(&edge.source()) && ring.contains(&edge.target()) => 1,
+                Some(edge) if aromatic.contains(&edge.id()) => 1,
+                None if lone_pair => 2,
+                _ => return false,
+            };
+        }
+        electrons % 4 == 2
+    }
+
+    /// Breadth-first path between the atoms avoiding the bond
+    fn shortest_path(
+        &self,
+        from: NodeIndex,
+        to: NodeIndex,
+        without: EdgeIndex,
+    ) -> Option<Vec<NodeIndex>> {
+        let mut parents = HashMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+        while let Some(atom) = queue.pop_front() {
+            if atom == to {
+                let mut path = vec![to];
+                while let Some(&parent) =
+                    parents.get(path.last()?).filter(|&&parent| parent != from)
+                {
+                    path.push(parent);
+                }
+                path.push(from);
+                return Some(path);
+            }
+            for edge in self.edges(atom).filter(|edge| edge.id() != without) {
+                let next = if edge.source() == atom {
+                    edge.target()
+                } else {
+                    edge.source()
+                };
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(atom);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Records of the library containing the query (with their index)
+pub fn filter<'a>(
+    records: &'a [Record],
+    query: &'a Query,
+) -> impl Iterator<Item = (usize, &'a Record)> {
+    records
+        .iter()
+        .enumerate()
+        .filter(|(_, record)| record.molecule.contains(query))
+}
+
+/// Backtracking matcher
+struct Matcher<'a> {
+    molecule: &'a Molecule,
+    query: &'a Query,
+    aromatic: HashSet<EdgeIndex>,
+    /// Query atoms in breadth-first order
+    order: Vec<NodeIndex>,
+    mapping: Vec<Option<NodeIndex>>,
+    used: HashSet<NodeIndex>,
+    /// Stop at the first mapping
+    first: bool,
+    mappings: Vec<Vec<NodeIndex>>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(molecule: &'a Molecule, query: &'a Query, first: bool) -> Self {
+        let mut order = Vec::new();
+        let mut visited = HashSet::new();
+        for start in query.node_indices() {
+            if !visited.insert(start) {
+                continue;
+            }
+            let mut queue = VecDeque::from([start]);
+            while let Some(atom) = queue.pop_front() {
+                order.push(atom);
+                for neighbor in query.neighbors(atom) {
+                    if visited.insert(neighbor) {
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        Self {
+            molecule,
+            query,
+            aromatic: molecule.aromatic_bonds(),
+            order,
+            mapping: vec![None; query.node_count()],
+            used: HashSet::new(),
+            first,
+            mappings: Vec::new(),
+        }
+    }
+
+    /// Returns whether to stop
+    fn search(&mut self, depth: usize) -> bool {
+        let Some(&atom) = self.order.get(depth) else {
+            self.mappings
+                .push(self.mapping.iter().copied().flatten().collect());
+            return self.first;
+        };
+        let mapped = self
+            .query
+            .neighbors(atom)
+            .find_map(|neighbor| self.mapping[neighbor.index()]);
+        let candidates = match mapped {
+            Some(mapped) => self.molecule.neighbors(mapped).collect::<Vec<_>>(),
+            None => self.molecule.node_indices().collect(),
+        };
+        for candidate in candidates {
+            if self.used.contains(&candidate) || !self.feasible(atom, candidate) {
+                continue;
+            }
+            self.mapping[atom.index()] = Some(candidate);
+            self.used.insert(candidate);
+            let stop = self.search(depth + 1);
+            self.used.remove(&candidate);
+            self.mapping[atom.index()] = None;
+            if stop {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn feasible(&self, atom: NodeIndex, candidate: NodeIndex) -> bool {
+        let molecule = self.molecule;
+        let matches = match self.query[atom] {
+            AtomQuery::Any => true,
+            AtomQuery::Element(element) => molecule[candidate] == element,
+            AtomQuery::Aromatic => molecule
+                .edges(candidate)
+                .any(|edge| self.aromatic.contains(&edge.id())),
+        };
+        if !matches || molecule.neighbors(candidate).count() < self.query.neighbors(atom).count() {
+            return false;
+        }
+        self.query.edges(atom).all(|edge| {
+            let neighbor = if edge.source() == atom {
+                edge.target()
+            } else {
+                edge.source()
+            };
+            let Some(mapped) = self.mapping[neighbor.index()] else {
+                return true;
+            };
+            let Some(bond) = molecule.find_edge(candidate, mapped) else {
+                return false;
+            };
+            let aromatic = self.aromatic.contains(&bond);
+            match *edge.weight() {
+                BondQuery::Any => true,
+                BondQuery::Order(order) => !aromatic && molecule[bond] == order,
+                BondQuery::Aromatic => aromatic,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::widget::atom::{C, O};
+
+    fn query(smiles: &str) -> Query {
+        Query::from(&smiles.parse::<Molecule>().unwrap())
+    }
+
+    #[test]
+    fn aromaticity() {
+        let count = |smiles: &str| smiles.parse::<Molecule>().unwrap().aromatic_bonds().len();
+        assert_eq!(count("c1ccccc1"), 6);
+        assert_eq!(count("c1ccncc1"), 6);
+        assert_eq!(count("c1cc[nH]c1"), 5);
+        assert_eq!(count("c1ccc2ccccc2c1"), 11);
+        assert_eq!(count("C1=CCCCC1"), 0);
+        assert_eq!(count("C1CCCCC1"), 0);
+    }
+
+    #[test]
+    fn substructures() {
+        let toluene = "Cc1ccccc1".parse::<Molecule>().unwrap();
+        assert_eq!(toluene.substructures(&query("c1ccccc1")).len(), 12);
+        assert!(toluene.contains(&query("Cc1ccccc1")));
+        assert!(!toluene.contains(&query("C=C")));
+        let ester = "CCOC(=O)C".parse::<Molecule>().unwrap();
+        let mut carbonyl = Query::default();
+        let any = carbonyl.add_node(AtomQuery::Any);
+        let c = carbonyl.add_node(AtomQuery::Element(C));
+        let o = carbonyl.add_node(AtomQuery::Element(O));
+        carbonyl.add_edge(any, c, BondQuery::Any);
+        carbonyl.add_edge(c, o, BondQuery::Order(2));
+        let mappings = ester.substructures(&carbonyl);
+        // The methyl carbon and the ester oxygen
+        assert_eq!(mappings.len(), 2);
+        assert!(mappings.iter().all(|mapping| ester[mapping[2]] == O));
+        let mut aromatic = Query::default();
+        aromatic.add_node(AtomQuery::Aromatic);
+        assert_eq!(toluene.substructures(&aromatic).len(), 6);
+        let records = ["c1ccccc1", "CCO", "Oc1ccccc1"].map(|smiles| Record {
+            molecule: smiles.parse().unwrap(),
+            ..Default::default()
+        });
+        let indices = filter(&records, &query("c1ccccc1"))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [0, 2]);
+    }
+
+    #[test]
+    fn any() {
+        let benzene = "c1ccccc1".parse::<Molecule>().unwrap();
+        assert!(!benzene.contains(&query("c1ccncc1")));
+        assert!(benzene.contains(&query("c1ccncc1").any_element()));
+        // The aromatic atoms only
+        let cyclohexane = "C1CCCCC1".parse::<Molecule>().unwrap();
+        assert!(!cyclohexane.contains(&query("c1ccncc1").any_element().any_bond()));
+        let ethanol = "CCO".parse::<Molecule>().unwrap();
+        assert!(!ethanol.contains(&query("CC=O")));
+        assert!(ethanol.contains(&query("CC=O").any_bond()));
+        assert!(ethanol.contains(&query("CC=C").any_element().any_bond()));
+    }
+}
@@ -0,0 +1,223 @@
+//! Valences: the bonds of the atoms checked against the allowed valences of
+//! their elements, formal charges and implicit hydrogens.
+//!
+//! An atom whose bonds fit none of the valences is explained by a formal
+//! charge: an onium of an atom with lone pairs (`[NH4+]`, `[OH3+]`) or an
+//! anion of an atom without bonds enough (`[O-]`, `[BH4-]`), and a cation of
+//! an electron deficient atom (`[CH3+]`).
+
+use super::{
+    atom::{Element, H},
+    molecule::Molecule,
+};
+use petgraph::graph::NodeIndex;
+use std::fmt::{self, Display, Formatter};
+
+/// Period ends of the periodic table
+const PERIODS: [u8; 7] = [2, 10, 18, 36, 54, 86, 118];
+
+impl Element {
+    /// Valence electrons of a main group element
+    pub fn valence_electrons(&self) -> Option<u8> {
+        let period = PERIODS.iter().position(|&end| self.number <= end)?;
+        let start = period.checked_sub(1).map_or(0, |period| PERIODS[period]);
+        let position = self.number - start;
+        let length = PERIODS[period] - start;
+        match (period, position) {
+            (0, 1) => Some(1),
+            (0, _) => Some(8),
+            (_, 1 | 2) => Some(position),
+            (_, _) if position + 6 > length => Some(position + 8 - length),
+            _ => None,
+        }
+    }
+
+    /// Implicit hydrogens to the lowest valence fitting the bonds
+    pub fn implicit_hydrogens(&self, bonds: usize) -> usize {
+        self.valences
+            .iter()
+            .map(|&valence| valence as usize)
+            .find(|&valence| valence >= bonds)
+            .map_or(0, |valence| valence - bonds)
+    }
+
+    /// Formal charge explaining the bonds (zero if they fit a valence)
+    pub fn formal_charge(&self, bonds: usize) -> Option<i8> {
+        let valences = self.valences.iter().map(|&valence| valence as usize);
+        if valences.clone().any(|valence| valence == bonds) {
+            return Some(0);
+        }
+        let electrons = self.valence_electrons()? as isize;
+        let lowest = valences.clone().min()?;
+        // Duet of hydrogen, octet of the second period
+        let shells = match self.number {
+            1..=2 => Some(1),
+            3..=10 => Some(4),
+            _ => None,
+        };
+        if shells.is_some_and(|shells| bonds > shells) {
+            return None;
+        }
+        let charge = if electrons > 4 {
+            let valence = valences.filter(|&valence| valence < bonds).max();
+            let charge = bonds as isize - valence.unwrap_or(lowest) as isize;
+            // An onium shares a lone pair
+            if electrons - charge - (bonds as isize) < 0 {
+                return None;
+            }
+            charge
+        } else {
+            lowest as isize - bonds as isize
+        };
+        charge.try_into().ok()
+    }
+}
+
+impl Molecule {
+    /// Sum of the bond orders of the atom
+    pub fn bonds(&self, index: NodeIndex) -> usize {
+        self.edges(index).map(|edge| *edge.weight() as usize).sum()
+    }
+
+    /// Implicit hydrogens of the atom (to its lowest valence fitting the
+    /// bonds)
+    pub fn implicit_hydrogens(&self, index: NodeIndex) -> usize {
+        self[index].implicit_hydrogens(self.bonds(index))
+    }
+
+    /// Formal charge of the atom (`None` if its bonds can't be explained)
+    pub fn formal_charge(&self, index: NodeIndex) -> Option<i8> {
+        self[index].formal_charge(self.bonds(index))
+    }
+
+    /// Total formal charge
+    pub fn charge(&self) -> Option<i8> {
+        self.node_indices()
+            .map(|index| self.formal_charge(index))
+            .sum()
+    }
+
+    /// Atoms whose bonds fit none of the valences of their elements
+    pub fn violations(&self) -> Vec<Violation> {
+        self.node_indices()
+            .filter_map(|index| {
+                let element = self[index];
+                let bonds = self.bonds(index);
+                let charge = element.formal_charge(bonds);
+                (charge != Some(0)).then_some(Violation {
+                    atom: index,
+                    element,
+                    bonds,
+                    charge,
+                })
+            })
+            .collect()
+    }
+
+    /// Completes a heavy-atom skeleton with the implicit hydrogens (returns
+    /// the number of the added ones)
+    pub fn add_hydrogens(&mut self) -> usize {
+        let mut count = 0;
+        let atoms = self.node_indices().collect::<Vec<_>>();
+        for index in atoms {
+            if self[index] == H {
+                continue;
+            }
+            for _ in 0..self.implicit_hydrogens(index) {
+                let hydrogen = self.add_node(H);
+                self.add_edge(index, hydrogen, 1);
+                count += 1;
+            }
+        }
+        count
+    }
+}
+
+/// Valence violation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    pub atom: NodeIndex,
+    pub element: &'static Element,
+    /// Sum of the bond orders
+    pub bonds: usize,
+    /// Formal charge explaining the bonds (`None` if they can't be explained)
+    pub charge: Option<i8>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}: {} bonds",
+            self.element,
+            self.atom.index() + 1,
+            self.bonds
+        )?;
+        match self.charge {
+            Some(charge) => write!(f, " (formal charge {charge:+})"),
+            None => {
+                let valences = self.element.valences.iter().map(u8::to_string);
+                write!(f, " (valences {})", valences.collect::<Vec<_>>().join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::widget::atom::{C, N, O};
+
+    #[test]
+    fn charges() {
+        assert_eq!(C.valence_electrons(), Some(4));
+        assert_eq!(Element::find("Br").unwrap().valence_electrons(), Some(7));
+        assert_eq!(Element::find("Fe").unwrap().valence_electrons(), None);
+        assert_eq!(N.formal_charge(4), Some(1));
+        assert_eq!(O.formal_charge(1), Some(-1));
+        assert_eq!(O.formal_charge(3), Some(1));
+        assert_eq!(C.formal_charge(3), Some(1));
+        let boron = Element::find("B").unwrap();
+        assert_eq!(boron.formal_charge(4), Some(-1));
+        assert_eq!(C.formal_charge(5), None);
+        assert_eq!(H.formal_charge(2), None);
+    }
+
+    #[test]
+    fn violations() {
+        let molecule = "CC(=O)O".parse::<Molecule>().unwrap();
+        assert!(molecule.violations().is_empty());
+        assert_eq!(molecule.charge(), Some(0));
+        let molecule = "C[N](C)(C)C".parse::<Molecule>().unwrap();
+        let violations = molecule.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].to_string(), "N2: 4 bonds (formal charge +1)");
+        assert_eq!(molecule.charge(), Some(1));
+        let molecule = "CC(C)(C)(C)C".parse::<Molecule>().unwrap();
+        let violations = molecule.violations();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].to_string(), "C2: 5 bonds (valences 4)");
+        assert_eq!(molecule.charge(), None);
+    }
+
+    #[test]
+    fn alkenes() {
+        for smiles in ["C=C", "C=CC", "C=CCC", "C=CCCC"] {
+            let molecule = smiles.parse::<Molecule>().unwrap();
+            assert!(molecule.violations().is_empty());
+            assert_eq!(molecule.charge(), Some(0));
+        }
+    }
+
+    #[test]
+    fn hydrogens() {
+        let mut molecule = Molecule::new();
+        let c = [(); 3].map(|_| molecule.add_node(C));
+        molecule.add_edge(c[0], c[1], 2);
+        molecule.add_edge(c[1], c[2], 1);
+        assert_eq!(molecule.add_hydrogens(), 6);
+        assert!(molecule.violations().is_empty());
+        assert_eq!(molecule.smiles(), "C=CC");
+        assert_eq!(molecule.add_hydrogens(), 0);
+    }
+}